@@ -0,0 +1,122 @@
+//! Automatic minimization driven by Hyprland focus events.
+//!
+//! This module watches `activewindow` events and moves the managed window to
+//! its special workspace when it loses focus, either immediately or after a
+//! configurable period of inactivity.
+
+use crate::config::AppConfig;
+use crate::events::Event;
+use crate::hyprland::{self, HyprlandBackend, ManagedWindow};
+use crate::state::DaemonState;
+use crate::thumbnail::ThumbnailSlot;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Duration, Instant};
+
+/// Returns true if the app has any auto-minimize rule configured.
+pub fn is_enabled(app_config: &AppConfig) -> bool {
+    app_config.auto_minimize_on_unfocus.unwrap_or(false)
+        || app_config.auto_minimize_after_secs.is_some()
+}
+
+/// Consumes focus events and minimizes the window according to the app's rules.
+///
+/// Runs until the event channel is closed.
+pub async fn run(
+    backend: Arc<dyn HyprlandBackend>,
+    mut events: mpsc::UnboundedReceiver<Event>,
+    app_config: AppConfig,
+    window: ManagedWindow,
+    state: Arc<DaemonState>,
+    thumbnails: ThumbnailSlot,
+) {
+    let on_unfocus = app_config.auto_minimize_on_unfocus.unwrap_or(false);
+    let after = app_config.auto_minimize_after_secs.map(Duration::from_secs);
+
+    // The window may already have focus when the daemon starts
+    let mut focused = match backend.active_window_address() {
+        Ok(address) => address == Some(window.address()),
+        Err(e) => {
+            eprintln!("[AutoMinimize] Failed to get the focused window: {}", e);
+            false
+        }
+    };
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let event = match deadline {
+            Some(at) => tokio::select! {
+                event = events.recv() => event,
+                _ = sleep_until(at) => {
+                    deadline = None;
+                    println!("[AutoMinimize] Window inactive for too long - minimizing");
+                    minimize(&*backend, &app_config, &window.address(), &state, &thumbnails);
+                    continue;
+                }
+            },
+            None => events.recv().await,
+        };
+
+        let address = match event {
+            Some(Event::ActiveWindow { address }) => address,
+            Some(_) => continue,
+            None => break,
+        };
+
+        let now_focused = owns_focus(&*backend, &app_config, &window.address(), address.as_deref());
+        if now_focused {
+            deadline = None;
+        } else if focused {
+            if on_unfocus {
+                println!("[AutoMinimize] Window lost focus - minimizing");
+                minimize(&*backend, &app_config, &window.address(), &state, &thumbnails);
+            } else if let Some(after) = after {
+                deadline = Some(Instant::now() + after);
+            }
+        }
+        focused = now_focused;
+    }
+}
+
+/// Returns true if the focused window is the managed one or another window of the app.
+///
+/// Dialogs of the app and its companions keep the window focused, so opening a
+/// file chooser does not minimize the window behind it.
+fn owns_focus(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    window_address: &str,
+    focused: Option<&str>,
+) -> bool {
+    let Some(focused) = focused else {
+        return false;
+    };
+    if focused == window_address {
+        return true;
+    }
+    match backend.clients() {
+        Ok(clients) => clients
+            .iter()
+            .find(|c| c.address == focused)
+            .is_some_and(|c| c.class == app_config.class || app_config.is_companion(&c.class, &c.title)),
+        Err(e) => {
+            eprintln!("[AutoMinimize] Failed to look up the focused window: {}", e);
+            false
+        }
+    }
+}
+
+/// Minimizes the window, logging any failure.
+fn minimize(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    window_address: &str,
+    state: &DaemonState,
+    thumbnails: &ThumbnailSlot,
+) {
+    match hyprland::minimize_window(backend, app_config, window_address, thumbnails) {
+        Ok(true) => state.set_minimized(true),
+        Ok(false) => {}
+        Err(e) => eprintln!("[AutoMinimize] Failed to minimize window: {}", e),
+    }
+}
@@ -0,0 +1,195 @@
+//! Autostart installation module.
+//!
+//! This module writes login autostart entries for applications configured
+//! with `launch_in_background = true`, either as XDG autostart `.desktop`
+//! files or as `exec-once` lines in the Hyprland configuration.
+
+use crate::config::{self, AppConfig, Config};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where autostart entries are installed.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutostartTarget {
+    /// XDG autostart `.desktop` entries in ~/.config/autostart
+    Desktop,
+    /// `exec-once` lines appended to hyprland.conf
+    Hyprland,
+}
+
+/// Returns the default path of the Hyprland configuration file.
+pub fn default_hyprland_conf() -> PathBuf {
+    config::config_home().join("hypr").join("hyprland.conf")
+}
+
+/// Installs autostart entries for the selected apps.
+///
+/// If `apps` is empty, every app with `launch_in_background = true` is selected.
+/// Explicitly selected apps without that option are skipped with a warning.
+pub fn install(
+    config: &Config,
+    apps: &[String],
+    target: AutostartTarget,
+    hyprland_conf: Option<&Path>,
+) -> Result<()> {
+    let selected = select_apps(config, apps)?;
+    if selected.is_empty() {
+        println!("[Autostart] No apps with launch_in_background = true found. Nothing to do.");
+        return Ok(());
+    }
+
+    let exe = executable();
+    match target {
+        AutostartTarget::Desktop => {
+            let dir = config::config_home().join("autostart");
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create autostart directory: {:?}", dir))?;
+            for (app_name, app_config) in &selected {
+                let path = dir.join(format!("hyprland-minimizer-{}.desktop", app_name));
+                fs::write(&path, desktop_entry(&exe, app_name, app_config))
+                    .with_context(|| format!("Failed to write autostart entry: {:?}", path))?;
+                println!("[Autostart] Wrote {:?}", path);
+            }
+        }
+        AutostartTarget::Hyprland => {
+            let path = hyprland_conf
+                .map(Path::to_path_buf)
+                .unwrap_or_else(default_hyprland_conf);
+            append_exec_once(&path, &exe, &selected)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves the list of apps to install, validating explicit app names.
+pub fn select_apps<'a>(config: &'a Config, apps: &[String]) -> Result<Vec<(&'a str, &'a AppConfig)>> {
+    let explicit = !apps.is_empty();
+    let selected = config
+        .select_apps(apps)?
+        .into_iter()
+        .filter(|(name, app_config)| {
+            let enabled = app_config.launch_in_background.unwrap_or(false);
+            if explicit && !enabled {
+                eprintln!(
+                    "[Autostart] Skipping '{}': launch_in_background is not enabled",
+                    name
+                );
+            }
+            enabled
+        })
+        .collect();
+    Ok(selected)
+}
+
+/// Returns the command used to start the minimizer in autostart entries.
+fn executable() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|p| p.to_str().map(str::to_string))
+        .unwrap_or_else(|| "hyprland-minimizer".to_string())
+}
+
+/// Builds the contents of an XDG autostart `.desktop` entry.
+fn desktop_entry(exe: &str, app_name: &str, app_config: &AppConfig) -> String {
+    let icon = app_config.icon_name();
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={name} (Hyprland Minimizer)\n\
+         Comment=Start {name} minimized to the tray\n\
+         Exec={exec}\n\
+         Icon={icon}\n\
+         Terminal=false\n\
+         OnlyShowIn=Hyprland;\n\
+         X-GNOME-Autostart-enabled=true\n",
+        name = app_config.name,
+        exec = desktop_exec(&[exe, app_name]),
+    )
+}
+
+/// Characters that force quoting of an `Exec` argument in a `.desktop` file.
+const DESKTOP_RESERVED: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')', '`',
+];
+
+/// Builds an `Exec` value from the given arguments, quoted per the Desktop Entry spec.
+///
+/// Arguments with reserved characters are wrapped in double quotes with `"`,
+/// `` ` ``, `$` and `\` escaped; literal `%` is doubled so it is not read as a
+/// field code. Backslashes are escaped once more for the string value itself.
+pub fn desktop_exec(args: &[&str]) -> String {
+    args.iter()
+        .map(|arg| {
+            let arg = arg.replace('%', "%%");
+            let arg = if arg.is_empty() || arg.contains(DESKTOP_RESERVED) {
+                let mut quoted = String::from("\"");
+                for c in arg.chars() {
+                    if matches!(c, '"' | '`' | '$' | '\\') {
+                        quoted.push('\\');
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('"');
+                quoted
+            } else {
+                arg
+            };
+            arg.replace('\\', "\\\\")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quotes an argument for the shell that runs Hyprland `exec-once` commands.
+fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "/._-+=:,@".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Appends `exec-once` lines to the Hyprland config, skipping lines already present.
+pub fn append_exec_once(path: &Path, exe: &str, apps: &[(&str, &AppConfig)]) -> Result<()> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let missing: Vec<String> = apps
+        .iter()
+        .map(|(app_name, _)| format!("exec-once = {} {}", shell_quote(exe), shell_quote(app_name)))
+        .filter(|line| !existing.lines().any(|l| l.trim() == line))
+        .collect();
+
+    if missing.is_empty() {
+        println!("[Autostart] {:?} already contains all entries.", path);
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {:?}", parent))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open Hyprland config: {:?}", path))?;
+
+    let mut block = String::new();
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        block.push('\n');
+    }
+    block.push_str("\n# Added by hyprland-minimizer install-autostart\n");
+    for line in &missing {
+        block.push_str(line);
+        block.push('\n');
+        println!("[Autostart] Added: {}", line);
+    }
+    file.write_all(block.as_bytes())
+        .with_context(|| format!("Failed to write Hyprland config: {:?}", path))?;
+
+    Ok(())
+}
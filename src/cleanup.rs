@@ -0,0 +1,167 @@
+//! Crash-safe cleanup of the managed window and runtime files.
+//!
+//! If the daemon dies while the window sits in its special workspace, the
+//! window is stranded without a tray icon to bring it back. A panic hook and
+//! an RAII guard make sure that on any abnormal exit the window is moved back
+//! to its original workspace and the lock and state files are removed. On an
+//! orderly stop (SIGTERM, Ctrl+C) the app's `on_exit` policy decides instead.
+
+use crate::config::{AppConfig, ExitPolicy};
+use crate::hyprland::{self, HyprlandBackend, ManagedWindow};
+use crate::lock;
+use crate::state::DaemonState;
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Exit code used after a panic, matching the one of an uncaught panic.
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// Everything needed to leave the session in a clean state.
+#[derive(Clone)]
+pub struct Cleanup {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    app_name: String,
+    app_config: AppConfig,
+    window: ManagedWindow,
+    state: Arc<DaemonState>,
+    backend: Arc<dyn HyprlandBackend>,
+    released: AtomicBool,
+}
+
+impl Cleanup {
+    /// Creates the cleanup for a running daemon.
+    pub fn new(
+        app_name: &str,
+        app_config: AppConfig,
+        window: ManagedWindow,
+        state: Arc<DaemonState>,
+        backend: Arc<dyn HyprlandBackend>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                app_name: app_name.to_string(),
+                app_config,
+                window,
+                state,
+                backend,
+                released: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Installs a panic hook that restores the window, releases the runtime
+    /// files and exits.
+    ///
+    /// Panics in spawned tasks would otherwise leave a half-working daemon
+    /// behind, so any panic ends the process.
+    pub fn install_panic_hook(&self) {
+        let cleanup = self.clone();
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            default_hook(info);
+            eprintln!("[Cleanup] Daemon panicked - restoring window before exiting");
+            cleanup.restore_and_release();
+            std::process::exit(PANIC_EXIT_CODE);
+        }));
+    }
+
+    /// Returns a guard that restores the window if dropped before [`CleanupGuard::disarm`].
+    pub fn guard(&self) -> CleanupGuard {
+        CleanupGuard {
+            cleanup: self.clone(),
+            armed: true,
+        }
+    }
+
+    /// Moves the window and its companions back to the original workspace if
+    /// they are still hidden.
+    ///
+    /// Windows that started out minimized go to the active workspace instead.
+    /// Returns `Ok(false)` if there was nothing to restore.
+    pub fn restore_window(&self) -> Result<bool> {
+        let backend = &*self.inner.backend;
+        let window = self.inner.window.get();
+        let clients = backend.clients()?;
+        if !clients.iter().any(|c| c.address == window.address && c.workspace.id < 0) {
+            return Ok(false);
+        }
+
+        let target = if window.workspace.id < 0 {
+            "+0".to_string()
+        } else {
+            window.workspace.id.to_string()
+        };
+        hyprland::move_companions(backend, &self.inner.app_config, &clients, &window.address, &target)?;
+        backend.dispatch(&format!("movetoworkspacesilent {},address:{}", target, window.address))?;
+        Ok(true)
+    }
+
+    /// Removes the lock and state files. Only the first call has an effect.
+    pub fn release(&self) {
+        if self.inner.released.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        lock::release_lock(&self.inner.app_name);
+        self.inner.state.remove();
+    }
+
+    /// Applies the app's `on_exit` policy when the daemon is asked to stop.
+    pub fn apply_exit_policy(&self) {
+        let result = match self.inner.app_config.on_exit {
+            ExitPolicy::Restore => self.restore_window().map(|restored| {
+                if restored {
+                    println!("[Cleanup] Window moved back out of the special workspace");
+                }
+            }),
+            ExitPolicy::LeaveMinimized => Ok(()),
+            ExitPolicy::CloseWindow => {
+                println!("[Cleanup] Closing window");
+                let address = self.inner.window.address();
+                hyprland::close_window_group(&*self.inner.backend, &self.inner.app_config, &address)
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("[Cleanup] Failed to apply exit policy: {}", e);
+        }
+    }
+
+    /// Restores the window and releases the runtime files, logging failures.
+    pub fn restore_and_release(&self) {
+        if !self.inner.released.load(Ordering::SeqCst) {
+            match self.restore_window() {
+                Ok(true) => println!("[Cleanup] Window moved back out of the special workspace"),
+                Ok(false) => {}
+                Err(e) => eprintln!("[Cleanup] Failed to restore window: {}", e),
+            }
+        }
+        self.release();
+    }
+}
+
+/// Restores the window and releases the runtime files when dropped while armed.
+///
+/// Covers early returns with an error after the window was taken over.
+pub struct CleanupGuard {
+    cleanup: Cleanup,
+    armed: bool,
+}
+
+impl CleanupGuard {
+    /// Marks the exit as orderly; dropping the guard no longer restores the window.
+    pub fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        if self.armed {
+            eprintln!("[Cleanup] Daemon stopped unexpectedly - restoring window");
+            self.cleanup.restore_and_release();
+        }
+    }
+}
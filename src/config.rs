@@ -4,12 +4,67 @@
 //! from TOML files. It manages application-specific settings including
 //! window classes, icons, launch commands, and behavior options.
 
+use crate::i18n::LabelOverrides;
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
+/// Returns the user's base configuration directory.
+/// Uses XDG_CONFIG_HOME if set, otherwise falls back to ~/.config
+pub fn config_home() -> PathBuf {
+    std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+                .join(".config")
+        })
+}
+
+/// Where a window is restored to when toggled back from the special workspace.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestorePolicy {
+    /// The workspace with keyboard focus
+    #[default]
+    Active,
+    /// The workspace on the monitor under the mouse cursor
+    FollowCursor,
+}
+
+/// Dispatchers run on a restored window unless the app sets `restore_dispatch`.
+pub const DEFAULT_RESTORE_DISPATCH: &[&str] = &[
+    "centerwindow",
+    "alterzorder top,address:{address}",
+];
+
+/// What to do when another window of the app's class opens.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SingletonPolicy {
+    /// Leave the new window alone
+    #[default]
+    Off,
+    /// Close the new window and focus the managed one
+    Focus,
+    /// Manage the new window instead of the current one
+    Adopt,
+}
+
+/// What to do with the window when the daemon is stopped by SIGTERM or Ctrl+C.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExitPolicy {
+    /// Move the window back out of the special workspace
+    #[default]
+    Restore,
+    /// Keep the window hidden in the special workspace
+    LeaveMinimized,
+    /// Close the window and its companions
+    CloseWindow,
+}
+
 /// Configuration for a single managed application.
 #[derive(Deserialize, Debug, Clone)]
 pub struct AppConfig {
@@ -25,8 +80,68 @@ pub struct AppConfig {
     pub notify_name: Option<String>,
     /// Whether to launch app directly in hidden special workspace
     pub launch_in_background: Option<bool>,
+    /// Whether to also ask the Flatpak background portal for autostart at login (default: false)
+    pub portal_autostart: Option<bool>,
     /// Maximum time to wait for application launch in seconds (default: 10)
     pub launch_timeout: Option<u64>,
+    /// Whether to minimize the window as soon as it loses focus
+    pub auto_minimize_on_unfocus: Option<bool>,
+    /// Minimize the window after it has been unfocused for this many seconds
+    pub auto_minimize_after_secs: Option<u64>,
+    /// Where to restore the window: "active" (default) or "follow_cursor"
+    #[serde(default)]
+    pub restore_policy: RestorePolicy,
+    /// Whether to show a thumbnail of the hidden window in the tray tooltip (requires grim)
+    pub tooltip_thumbnail: Option<bool>,
+    /// Classes of helper windows moved, restored and closed with the main window
+    #[serde(default)]
+    pub companion_classes: Vec<String>,
+    /// Title substrings of helper windows of the app's classes moved, restored and closed with the main window
+    #[serde(default)]
+    pub companion_titles: Vec<String>,
+    /// Name of the special workspace the window is hidden in (optional, defaults to class)
+    pub special_workspace: Option<String>,
+    /// What to do with a second window of the app: "off" (default), "focus" or "adopt"
+    #[serde(default)]
+    pub singleton_policy: SingletonPolicy,
+    /// Dispatchers run after restoring the window; `{address}`, `{class}` and `{workspace}` are substituted
+    pub restore_dispatch: Option<Vec<String>>,
+    /// What to do with the window on SIGTERM/Ctrl+C: "restore" (default), "leave-minimized" or "close-window"
+    #[serde(default)]
+    pub on_exit: ExitPolicy,
+}
+
+impl AppConfig {
+    /// Returns true if a window with this class and title is a companion of the app.
+    ///
+    /// Windows sharing the main window's class only match by title. Titles are
+    /// only checked for the app's own classes, so windows of unrelated apps are
+    /// never grouped with it.
+    pub fn is_companion(&self, class: &str, title: &str) -> bool {
+        let companion_class = self.companion_classes.iter().any(|c| c == class);
+        let class_match = class != self.class && companion_class;
+        let own_class = class == self.class || companion_class;
+        let title_match = own_class && self.companion_titles.iter().any(|t| title.contains(t.as_str()));
+        class_match || title_match
+    }
+
+    /// Returns the icon name shown in the tray, falling back to the class.
+    pub fn icon_name(&self) -> &str {
+        self.icon.as_deref().unwrap_or(&self.class)
+    }
+
+    /// Returns the dispatchers run after restoring the window.
+    pub fn restore_dispatch(&self) -> Vec<&str> {
+        match &self.restore_dispatch {
+            Some(commands) => commands.iter().map(String::as_str).collect(),
+            None => DEFAULT_RESTORE_DISPATCH.to_vec(),
+        }
+    }
+
+    /// Returns the name of the app's special workspace, without the `special:` prefix.
+    pub fn special_workspace(&self) -> &str {
+        self.special_workspace.as_deref().unwrap_or(&self.class)
+    }
 }
 
 /// Root configuration structure containing all managed apps.
@@ -34,6 +149,12 @@ pub struct AppConfig {
 pub struct Config {
     /// Map of app identifiers to their configurations
     pub apps: HashMap<String, AppConfig>,
+    /// Overrides for the tray menu labels
+    #[serde(default)]
+    pub labels: LabelOverrides,
+    /// Extra directories with custom icons not in the system theme
+    #[serde(default)]
+    pub icon_dirs: Vec<String>,
 }
 
 impl Config {
@@ -50,20 +171,84 @@ impl Config {
         let config_str = fs::read_to_string(&config_path)
             .with_context(|| format!("Failed to read config file: {:?}", config_path))?;
         
-        toml::from_str(&config_str)
-            .with_context(|| "Failed to parse config file")
+        let mut config: Self = toml::from_str(&config_str)
+            .with_context(|| "Failed to parse config file")?;
+        config.assign_special_workspaces();
+        Ok(config)
+    }
+
+    /// Resolves app names given on the command line.
+    ///
+    /// Returns every app sorted by name if `apps` is empty, and fails on the
+    /// first unknown name otherwise.
+    pub fn select_apps(&self, apps: &[String]) -> Result<Vec<(&str, &AppConfig)>> {
+        if apps.is_empty() {
+            let mut selected: Vec<_> = self.apps.iter().map(|(name, app)| (name.as_str(), app)).collect();
+            selected.sort_by_key(|(name, _)| *name);
+            return Ok(selected);
+        }
+        apps.iter()
+            .map(|name| {
+                self.apps
+                    .get_key_value(name)
+                    .map(|(name, app)| (name.as_str(), app))
+                    .with_context(|| format!("Unknown app '{}'", name))
+            })
+            .collect()
+    }
+
+    /// Gives apps that would share a special workspace one of their own.
+    ///
+    /// Apps without an explicit `special_workspace` whose class-derived name
+    /// collides with another app get `{class}-{app id}` instead, so toggling
+    /// one app never reveals another app's hidden window. Names that are still
+    /// shared afterwards were set explicitly and only trigger a warning.
+    pub fn assign_special_workspaces(&mut self) {
+        let mut usage: HashMap<String, usize> = HashMap::new();
+        for app in self.apps.values() {
+            *usage.entry(app.special_workspace().to_string()).or_default() += 1;
+        }
+
+        for (app_id, app) in self.apps.iter_mut() {
+            if usage[app.special_workspace()] < 2 || app.special_workspace.is_some() {
+                continue;
+            }
+            let name = format!("{}-{}", app.class, app_id);
+            eprintln!(
+                "[Config] App '{}' shares special workspace '{}', using '{}' instead",
+                app_id, app.class, name
+            );
+            app.special_workspace = Some(name);
+        }
+
+        for (name, apps) in self.shared_special_workspaces() {
+            eprintln!(
+                "[Config] Warning: special workspace '{}' is shared by apps {}",
+                name,
+                apps.join(", ")
+            );
+        }
+    }
+
+    /// Returns the special workspaces used by more than one app, with the
+    /// sorted ids of those apps.
+    pub fn shared_special_workspaces(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut users: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (app_id, app) in &self.apps {
+            users.entry(app.special_workspace()).or_default().push(app_id);
+        }
+        let mut shared: Vec<_> = users.into_iter().filter(|(_, apps)| apps.len() > 1).collect();
+        for (_, apps) in &mut shared {
+            apps.sort_unstable();
+        }
+        shared.sort_unstable();
+        shared
     }
     
     /// Returns the path to the configuration file.
     /// Uses XDG_CONFIG_HOME if set, otherwise falls back to ~/.config
     pub fn get_config_path() -> PathBuf {
-        let config_dir = std::env::var("XDG_CONFIG_HOME")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| {
-                PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-                    .join(".config")
-            });
-        config_dir.join("hyprland-minimizer").join("config.toml")
+        config_home().join("hyprland-minimizer").join("config.toml")
     }
     
     /// Creates a default configuration file by copying the example config.
@@ -1,15 +1,24 @@
 //! D-Bus interface implementations for system tray integration.
 //! 
 //! This module implements the StatusNotifierItem protocol (used by Waybar and
-//! other system trays) and the DBusMenu protocol for context menus.
-
-use crate::hyprland::{self, WindowInfo};
+//! other system trays) and the DBusMenu protocol for context menus. The same
+//! menu is also exported as a GMenuModel (`org.gtk.Menus` + `org.gtk.Actions`)
+//! for GTK-based tray hosts. A watchdog keeps both on the session bus when
+//! the connection drops.
+
+use crate::config::AppConfig;
+use crate::hyprland::{self, HyprlandBackend, ManagedWindow};
+use crate::i18n::Labels;
+use crate::state::DaemonState;
+use crate::thumbnail::{Pixmap, ThumbnailSlot};
 use std::collections::HashMap;
 use std::process::Command;
 use std::sync::Arc;
 use tokio::sync::Notify;
-use zbus::zvariant::{ObjectPath, Value};
-use zbus::dbus_interface;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_stream::StreamExt;
+use zbus::zvariant::{ObjectPath, OwnedValue, Signature, Value};
+use zbus::{dbus_interface, Connection, ConnectionBuilder, SignalContext};
 
 /// D-Bus service name for the StatusNotifierWatcher.
 pub const DBUS_WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
@@ -17,9 +26,33 @@ pub const DBUS_WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
 /// D-Bus object path for the StatusNotifierWatcher.
 pub const DBUS_WATCHER_PATH: &str = "/StatusNotifierWatcher";
 
+/// Action group prefix used by GMenu items (e.g. "app.toggle").
+pub const GTK_ACTION_PREFIX: &str = "app";
+
 /// Delay before re-registering with the watcher after it restarts.
 pub const REREGISTER_DELAY_MS: u64 = 100;
 
+/// Interval between D-Bus connection health checks.
+pub const WATCHDOG_INTERVAL_SECS: u64 = 5;
+
+/// Time the bus has to answer a health check.
+pub const WATCHDOG_TIMEOUT_SECS: u64 = 5;
+
+/// Delay between reconnection attempts after the connection was lost.
+pub const RECONNECT_DELAY_SECS: u64 = 2;
+
+/// A DBusMenu layout node: (id, properties, children).
+type MenuLayout<'a> = (i32, HashMap<String, Value<'a>>, Vec<Value<'a>>);
+
+/// A GMenuModel group: (group id, menu id, items).
+type GtkMenuGroup<'a> = (u32, u32, Vec<HashMap<String, Value<'a>>>);
+
+/// A GAction description: (enabled, parameter type, state).
+type GtkActionDescription = (bool, Signature<'static>, Vec<OwnedValue>);
+
+/// A StatusNotifierItem tooltip: (icon name, icon pixmaps, title, description).
+type ToolTip = (String, Vec<Pixmap>, String, String);
+
 /// Registers the status notifier item with the StatusNotifierWatcher.
 pub async fn register_with_watcher(conn: &zbus::Connection, bus_name: &str) -> anyhow::Result<()> {
     let watcher_proxy: zbus::Proxy<'_> = zbus::ProxyBuilder::new_bare(conn)
@@ -34,10 +67,303 @@ pub async fn register_with_watcher(conn: &zbus::Connection, bus_name: &str) -> a
     Ok(())
 }
 
+/// Entries of the tray context menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    Toggle,
+    Restore,
+    Close,
+}
+
+impl MenuAction {
+    /// All menu entries in display order.
+    pub const ALL: [MenuAction; 3] = [MenuAction::Toggle, MenuAction::Restore, MenuAction::Close];
+
+    /// Returns the DBusMenu item id.
+    pub fn id(self) -> i32 {
+        match self {
+            MenuAction::Toggle => 1,
+            MenuAction::Restore => 2,
+            MenuAction::Close => 3,
+        }
+    }
+
+    /// Returns the GAction name.
+    pub fn name(self) -> &'static str {
+        match self {
+            MenuAction::Toggle => "toggle",
+            MenuAction::Restore => "restore",
+            MenuAction::Close => "close",
+        }
+    }
+
+    /// Looks up a menu entry by DBusMenu item id.
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.id() == id)
+    }
+
+    /// Looks up a menu entry by GAction name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// Menu state shared by the DBusMenu and GMenu exports.
+pub struct MenuModel {
+    pub backend: Arc<dyn HyprlandBackend>,
+    pub app_config: AppConfig,
+    pub window_info: ManagedWindow,
+    pub exit_notify: Arc<Notify>,
+    pub state: Arc<DaemonState>,
+    pub labels: Labels,
+    pub thumbnails: ThumbnailSlot,
+}
+
+impl MenuModel {
+    /// Returns the localized label of a menu entry.
+    pub fn label(&self, action: MenuAction) -> String {
+        let window = self.window_info.get();
+        let title = &window.title;
+        match action {
+            MenuAction::Toggle => self.labels.toggle(title),
+            MenuAction::Restore => self.labels.restore(title, window.workspace.id),
+            MenuAction::Close => self.labels.close(title),
+        }
+    }
+
+    /// Runs the action behind a menu entry.
+    pub fn activate(&self, action: MenuAction) {
+        let window = self.window_info.get();
+        let backend = &*self.backend;
+        let res = match action {
+            MenuAction::Toggle => {
+                println!("[Menu] 'Toggle' action triggered.");
+                // Send signal to ourselves to toggle
+                let _ = Command::new("kill")
+                    .arg("-USR1")
+                    .arg(std::process::id().to_string())
+                    .status();
+                Ok(())
+            }
+            MenuAction::Restore => {
+                println!("[Menu] 'Restore to workspace' action triggered.");
+                backend
+                    .dispatch(&format!(
+                        "movetoworkspace {},address:{}",
+                        window.workspace.id, window.address
+                    ))
+                    .and_then(|_| {
+                        let clients = backend.clients()?;
+                        let target = window.workspace.id.to_string();
+                        hyprland::move_companions(backend, &self.app_config, &clients, &window.address, &target)
+                    })
+                    .and_then(|_| backend.dispatch(&format!("focuswindow address:{}", window.address)))
+                    .map(|_| {
+                        self.state.set_minimized(false);
+                        self.thumbnails.clear();
+                    })
+            }
+            MenuAction::Close => {
+                println!("[Menu] 'Close' action triggered.");
+                let result = hyprland::close_window_group(backend, &self.app_config, &window.address);
+                // Exit only when closing the window
+                self.exit_notify.notify_one();
+                result
+            }
+        };
+
+        if let Err(e) = res {
+            eprintln!("[Error] Failed to execute hyprctl dispatch from menu: {}", e);
+        }
+    }
+}
+
+/// Emits `NewToolTip` whenever the window thumbnail changes.
+///
+/// Runs until the connection's object server no longer serves the item.
+pub async fn forward_tooltip_changes(conn: &zbus::Connection, thumbnails: ThumbnailSlot) {
+    loop {
+        thumbnails.changed().await;
+        let iface = match conn
+            .object_server()
+            .interface::<_, StatusNotifierItem>("/StatusNotifierItem")
+            .await
+        {
+            Ok(iface) => iface,
+            Err(_) => return,
+        };
+        if let Err(e) = StatusNotifierItem::new_tool_tip(iface.signal_context()).await {
+            eprintln!("[D-Bus] Failed to emit NewToolTip: {}", e);
+        }
+    }
+}
+
+/// Emits `NewTitle` and `NewToolTip` whenever another window is adopted.
+///
+/// Runs until the connection's object server no longer serves the item.
+pub async fn forward_window_changes(conn: &zbus::Connection, window: ManagedWindow) {
+    loop {
+        window.changed().await;
+        let iface = match conn
+            .object_server()
+            .interface::<_, StatusNotifierItem>("/StatusNotifierItem")
+            .await
+        {
+            Ok(iface) => iface,
+            Err(_) => return,
+        };
+        if let Err(e) = StatusNotifierItem::new_title(iface.signal_context()).await {
+            eprintln!("[D-Bus] Failed to emit NewTitle: {}", e);
+        }
+        if let Err(e) = StatusNotifierItem::new_tool_tip(iface.signal_context()).await {
+            eprintln!("[D-Bus] Failed to emit NewToolTip: {}", e);
+        }
+    }
+}
+
+/// The bus the tray icon and menu are served on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusAddress {
+    /// The user's session bus
+    Session,
+    /// A bus at an explicit D-Bus address, e.g. "unix:path=/run/bus"
+    Custom(String),
+}
+
+impl BusAddress {
+    /// Returns a connection builder for this bus.
+    fn builder(&self) -> zbus::Result<ConnectionBuilder<'_>> {
+        match self {
+            BusAddress::Session => ConnectionBuilder::session(),
+            BusAddress::Custom(address) => ConnectionBuilder::address(address.as_str()),
+        }
+    }
+}
+
+/// Connects to the bus, claims `bus_name` and serves the tray icon and menu.
+pub async fn serve(
+    bus: &BusAddress,
+    bus_name: &str,
+    item: StatusNotifierItem,
+    menu: &Arc<MenuModel>,
+) -> zbus::Result<Connection> {
+    bus.builder()?
+        .name(bus_name)?
+        .serve_at("/StatusNotifierItem", item)?
+        .serve_at("/Menu", DbusMenu { model: Arc::clone(menu) })?
+        .serve_at("/Menu", GtkMenus { model: Arc::clone(menu) })?
+        .serve_at("/Menu", GtkActions { model: Arc::clone(menu) })?
+        .build()
+        .await
+}
+
+/// Re-registers the icon whenever the StatusNotifierWatcher (re)appears, e.g. after a Waybar restart.
+pub async fn reregister_on_watcher_restart(conn: &Connection, bus_name: &str) {
+    let dbus_proxy = match zbus::fdo::DBusProxy::new(conn).await {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("[Watcher] Failed to connect to D-Bus proxy: {}", e);
+            return;
+        }
+    };
+
+    let mut owner_changes = match dbus_proxy.receive_name_owner_changed().await {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("[Watcher] Failed to listen for owner changes: {}", e);
+            return;
+        }
+    };
+
+    println!("[Watcher] Watching for '{}' restarts...", DBUS_WATCHER_NAME);
+
+    while let Some(signal) = owner_changes.next().await {
+        if let Ok(args) = signal.args() {
+            if args.name() == DBUS_WATCHER_NAME && args.new_owner().is_some() {
+                println!("[Watcher] Tray service detected. Re-registering icon.");
+                sleep(Duration::from_millis(REREGISTER_DELAY_MS)).await;
+                if let Err(e) = register_with_watcher(conn, bus_name).await {
+                    eprintln!("[Watcher] Failed to re-register icon: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Returns true if the bus still answers on the proxy's connection.
+async fn is_alive(proxy: &zbus::fdo::DBusProxy<'_>) -> bool {
+    matches!(
+        timeout(Duration::from_secs(WATCHDOG_TIMEOUT_SECS), proxy.get_id()).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Keeps the tray icon and menu on the bus across connection losses.
+///
+/// Pings the bus periodically. When the connection is gone (bus restart,
+/// suspend glitches) a new one is built, both objects are served again and
+/// the icon is re-registered with the watcher. The title, tooltip and
+/// watcher tasks bound to the connection are restarted with it. Never returns.
+pub async fn watchdog(
+    bus: BusAddress,
+    mut conn: Connection,
+    bus_name: String,
+    item: StatusNotifierItem,
+    menu: Arc<MenuModel>,
+) {
+    loop {
+        let tasks = [
+            tokio::spawn({
+                let conn = conn.clone();
+                let thumbnails = item.thumbnails.clone();
+                async move { forward_tooltip_changes(&conn, thumbnails).await }
+            }),
+            tokio::spawn({
+                let conn = conn.clone();
+                let window = item.window_info.clone();
+                async move { forward_window_changes(&conn, window).await }
+            }),
+            tokio::spawn({
+                let conn = conn.clone();
+                let bus_name = bus_name.clone();
+                async move { reregister_on_watcher_restart(&conn, &bus_name).await }
+            }),
+        ];
+
+        // One proxy per connection, reused for every ping
+        match zbus::fdo::DBusProxy::new(&conn).await {
+            Ok(proxy) => {
+                while is_alive(&proxy).await {
+                    sleep(Duration::from_secs(WATCHDOG_INTERVAL_SECS)).await;
+                }
+            }
+            Err(e) => eprintln!("[Watchdog] Failed to create D-Bus proxy: {}", e),
+        }
+        eprintln!("[Watchdog] Lost the D-Bus session connection, reconnecting...");
+        for task in tasks {
+            task.abort();
+        }
+        // Release the bus name held by the old connection, if it is still around
+        drop(conn);
+
+        conn = loop {
+            sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+            match serve(&bus, &bus_name, item.clone(), &menu).await {
+                Ok(conn) => break conn,
+                Err(e) => eprintln!("[Watchdog] Failed to reconnect: {}", e),
+            }
+        };
+        match register_with_watcher(&conn, &bus_name).await {
+            Ok(()) => println!("[Watchdog] Reconnected and re-registered the tray icon"),
+            // The watcher task registers the icon once a tray shows up
+            Err(e) => eprintln!("[Watchdog] Reconnected, but failed to register icon: {}", e),
+        }
+    }
+}
+
 /// Implementation of the DBusMenu interface for the context menu.
 pub struct DbusMenu {
-    pub window_info: Arc<WindowInfo>,
-    pub exit_notify: Arc<Notify>,
+    pub model: Arc<MenuModel>,
 }
 
 #[dbus_interface(name = "com.canonical.dbusmenu")]
@@ -48,7 +374,7 @@ impl DbusMenu {
         _parent_id: i32,
         _recursion_depth: i32,
         _property_names: Vec<String>,
-    ) -> (u32, (i32, HashMap<String, Value<'_>>, Vec<Value<'_>>)) {
+    ) -> (u32, MenuLayout<'_>) {
         println!("[D-Bus Menu] GetLayout called.");
 
         let create_menu_item = |id: i32, label: String| -> Value {
@@ -58,14 +384,10 @@ impl DbusMenu {
             Value::from((id, props, Vec::<Value>::new()))
         };
 
-        let items = vec![
-            create_menu_item(1, format!("Toggle {}", self.window_info.title)),
-            create_menu_item(
-                2,
-                format!("Restore to workspace ({})", self.window_info.workspace.id),
-            ),
-            create_menu_item(3, format!("Close {}", self.window_info.title)),
-        ];
+        let items = MenuAction::ALL
+            .into_iter()
+            .map(|action| create_menu_item(action.id(), self.model.label(action)))
+            .collect();
 
         let mut root_props = HashMap::new();
         root_props.insert("children-display".to_string(), Value::from("submenu"));
@@ -86,13 +408,10 @@ impl DbusMenu {
         let mut result = Vec::new();
         for id in ids {
             let mut props = HashMap::new();
-            let label = match id {
-                1 => format!("Toggle {}", self.window_info.title),
-                2 => format!("Restore to workspace ({})", self.window_info.workspace.id),
-                3 => format!("Close {}", self.window_info.title),
-                _ => continue,
+            let Some(action) = MenuAction::from_id(id) else {
+                continue;
             };
-            props.insert("label".to_string(), Value::from(label));
+            props.insert("label".to_string(), Value::from(self.model.label(action)));
             props.insert("enabled".to_string(), Value::from(true));
             props.insert("visible".to_string(), Value::from(true));
             props.insert("type".to_string(), Value::from("standard"));
@@ -120,41 +439,9 @@ impl DbusMenu {
             return;
         }
 
-        let res = match id {
-            1 => {
-                println!("[D-Bus Menu] 'Toggle' action triggered.");
-                // Send signal to ourselves to toggle
-                let _ = Command::new("kill")
-                    .arg("-USR1")
-                    .arg(std::process::id().to_string())
-                    .status();
-                Ok(())
-            }
-            2 => {
-                println!("[D-Bus Menu] 'Restore to workspace' action triggered.");
-                hyprland::dispatch(&format!(
-                    "movetoworkspace {},address:{}",
-                    self.window_info.workspace.id, self.window_info.address
-                ))
-                .and_then(|_| {
-                    hyprland::dispatch(&format!("focuswindow address:{}", self.window_info.address))
-                })
-            }
-            3 => {
-                println!("[D-Bus Menu] 'Close' action triggered.");
-                let result = hyprland::dispatch(&format!("closewindow address:{}", self.window_info.address));
-                // Exit only when closing the window
-                self.exit_notify.notify_one();
-                result
-            }
-            _ => {
-                println!("[D-Bus Menu] Clicked on unknown item id: {}", id);
-                return;
-            }
-        };
-
-        if let Err(e) = res {
-            eprintln!("[Error] Failed to execute hyprctl dispatch from menu: {}", e);
+        match MenuAction::from_id(id) {
+            Some(action) => self.model.activate(action),
+            None => println!("[D-Bus Menu] Clicked on unknown item id: {}", id),
         }
     }
 
@@ -185,10 +472,111 @@ impl DbusMenu {
     }
 }
 
+/// Implementation of the org.gtk.Menus interface (GMenuModel export).
+///
+/// The menu is a single flat section (group 0, menu 0) whose items reference
+/// the actions exported by [`GtkActions`] under the [`GTK_ACTION_PREFIX`] prefix.
+pub struct GtkMenus {
+    pub model: Arc<MenuModel>,
+}
+
+#[dbus_interface(name = "org.gtk.Menus")]
+impl GtkMenus {
+    /// Subscribes to menu groups and returns their contents.
+    fn start(&self, groups: Vec<u32>) -> Vec<GtkMenuGroup<'_>> {
+        println!("[GMenu] Start called for groups: {:?}", groups);
+        if !groups.contains(&0) {
+            return Vec::new();
+        }
+
+        let items = MenuAction::ALL
+            .into_iter()
+            .map(|action| {
+                let mut item = HashMap::new();
+                item.insert("label".to_string(), Value::from(self.model.label(action)));
+                item.insert(
+                    "action".to_string(),
+                    Value::from(format!("{}.{}", GTK_ACTION_PREFIX, action.name())),
+                );
+                item
+            })
+            .collect();
+        vec![(0, 0, items)]
+    }
+
+    /// Unsubscribes from menu groups.
+    fn end(&self, groups: Vec<u32>) {
+        println!("[GMenu] End called for groups: {:?}", groups);
+    }
+}
+
+/// Implementation of the org.gtk.Actions interface (GActionGroup export).
+pub struct GtkActions {
+    pub model: Arc<MenuModel>,
+}
+
+#[dbus_interface(name = "org.gtk.Actions")]
+impl GtkActions {
+    /// Lists the names of all actions.
+    fn list(&self) -> Vec<String> {
+        MenuAction::ALL.iter().map(|a| a.name().to_string()).collect()
+    }
+
+    /// Describes a single action.
+    fn describe(&self, action: &str) -> zbus::fdo::Result<GtkActionDescription> {
+        MenuAction::from_name(action)
+            .map(|_| describe_action())
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("Unknown action '{}'", action)))
+    }
+
+    /// Describes all actions.
+    fn describe_all(&self) -> HashMap<String, GtkActionDescription> {
+        MenuAction::ALL
+            .iter()
+            .map(|a| (a.name().to_string(), describe_action()))
+            .collect()
+    }
+
+    /// Activates an action.
+    fn activate(
+        &self,
+        action: &str,
+        _parameter: Vec<OwnedValue>,
+        _platform_data: HashMap<String, OwnedValue>,
+    ) {
+        println!("[GMenu] Activate called for action '{}'", action);
+        match MenuAction::from_name(action) {
+            Some(action) => self.model.activate(action),
+            None => println!("[GMenu] Unknown action: {}", action),
+        }
+    }
+
+    /// Changes the state of a stateful action (none are stateful).
+    fn set_state(
+        &self,
+        action: &str,
+        _value: OwnedValue,
+        _platform_data: HashMap<String, OwnedValue>,
+    ) {
+        println!("[GMenu] Ignoring SetState for stateless action '{}'", action);
+    }
+}
+
+/// Describes a stateless, parameterless, enabled action.
+fn describe_action() -> GtkActionDescription {
+    (true, Signature::from_static_str_unchecked(""), Vec::new())
+}
+
 /// Implementation of the StatusNotifierItem protocol (system tray icon).
+#[derive(Clone)]
 pub struct StatusNotifierItem {
-    pub window_info: Arc<WindowInfo>,
+    pub backend: Arc<dyn HyprlandBackend>,
+    pub app_config: AppConfig,
+    pub window_info: ManagedWindow,
     pub exit_notify: Arc<Notify>,
+    /// Extra directory trays search for the icon (empty if none configured)
+    pub icon_theme_path: String,
+    pub thumbnails: ThumbnailSlot,
 }
 
 #[dbus_interface(name = "org.kde.StatusNotifierItem")]
@@ -201,12 +589,12 @@ impl StatusNotifierItem {
 
     #[dbus_interface(property)]
     fn id(&self) -> &str {
-        &self.window_info.class
+        &self.app_config.class
     }
 
     #[dbus_interface(property)]
-    fn title(&self) -> &str {
-        &self.window_info.title
+    fn title(&self) -> String {
+        self.window_info.get().title
     }
 
     #[dbus_interface(property)]
@@ -214,18 +602,40 @@ impl StatusNotifierItem {
         "Active"
     }
 
+    /// The configured `icon`, falling back to the class.
+    ///
+    /// `IconThemePath` points at the directory holding this same name, so
+    /// trays can only resolve custom icons if both use the configured icon.
     #[dbus_interface(property)]
     fn icon_name(&self) -> &str {
-        &self.window_info.class
+        self.app_config.icon_name()
+    }
+
+    #[dbus_interface(property)]
+    fn icon_theme_path(&self) -> &str {
+        &self.icon_theme_path
     }
 
     #[dbus_interface(property)]
-    fn tool_tip(&self) -> (String, Vec<(i32, i32, Vec<u8>)>, String, String) {
+    fn tool_tip(&self) -> ToolTip {
+        let window = self.window_info.get();
+        // List the companion windows currently grouped with the main window
+        let description = self
+            .backend
+            .clients()
+            .map(|clients| {
+                hyprland::find_companions(&self.app_config, &clients, &window.address)
+                    .iter()
+                    .map(|c| c.title.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
         (
             String::new(),
-            Vec::new(),
-            self.window_info.title.clone(),
-            String::new(),
+            self.thumbnails.get().into_iter().collect(),
+            window.title,
+            description,
         )
     }
 
@@ -239,6 +649,16 @@ impl StatusNotifierItem {
         ObjectPath::try_from("/Menu").unwrap()
     }
 
+    // --- Signals ---
+
+    /// Tells the tray to re-read the `Title` property.
+    #[dbus_interface(signal)]
+    pub async fn new_title(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    /// Tells the tray to re-read the `ToolTip` property.
+    #[dbus_interface(signal)]
+    pub async fn new_tool_tip(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
     // --- Methods ---
     
     /// Handles left-click on the tray icon.
@@ -255,7 +675,7 @@ impl StatusNotifierItem {
     fn secondary_activate(&self, _x: i32, _y: i32) {
         println!("[D-Bus] SecondaryActivate called (middle-click to close)");
         if let Err(e) =
-            hyprland::dispatch(&format!("closewindow address:{}", self.window_info.address))
+            hyprland::close_window_group(&*self.backend, &self.app_config, &self.window_info.address())
         {
             eprintln!("[Error] Failed to execute secondary_activate action: {}", e);
         }
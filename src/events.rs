@@ -0,0 +1,137 @@
+//! Hyprland event socket module.
+//!
+//! This module connects to Hyprland's event socket (`.socket2.sock`) and
+//! turns its line-based `EVENT>>DATA` stream into typed events, which can be
+//! shared by several consumers through [`fan_out`].
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::mpsc;
+
+/// An event received from the Hyprland event socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The focused window changed (`None` if no window is focused)
+    ActiveWindow { address: Option<String> },
+    /// A new window was mapped
+    OpenWindow {
+        address: String,
+        workspace: String,
+        class: String,
+        title: String,
+    },
+}
+
+/// Returns the path to the Hyprland event socket of the running instance.
+///
+/// Newer Hyprland versions place the socket under `$XDG_RUNTIME_DIR/hypr`,
+/// older ones under `/tmp/hypr`.
+pub fn socket_path() -> Result<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .context("HYPRLAND_INSTANCE_SIGNATURE is not set. Is Hyprland running?")?;
+
+    if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+        let path = PathBuf::from(runtime_dir)
+            .join("hypr")
+            .join(&signature)
+            .join(".socket2.sock");
+        if path.exists() {
+            return Ok(path);
+        }
+    }
+
+    Ok(PathBuf::from("/tmp/hypr").join(&signature).join(".socket2.sock"))
+}
+
+/// Parses a single line from the event socket.
+///
+/// Returns `None` for events this application does not care about.
+pub fn parse_event(line: &str) -> Option<Event> {
+    let (name, data) = line.split_once(">>")?;
+    match name {
+        "activewindowv2" => {
+            // Hyprland sends the address without the `0x` prefix used by hyprctl
+            let address = match data.trim() {
+                "" | "," => None,
+                addr => Some(format!("0x{}", addr.trim_start_matches("0x"))),
+            };
+            Some(Event::ActiveWindow { address })
+        }
+        "openwindow" => {
+            // The title comes last and may itself contain commas
+            let mut fields = data.trim_end().splitn(4, ',');
+            let address = fields.next()?;
+            Some(Event::OpenWindow {
+                address: format!("0x{}", address.trim_start_matches("0x")),
+                workspace: fields.next()?.to_string(),
+                class: fields.next()?.to_string(),
+                title: fields.next().unwrap_or_default().to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Connects to the Hyprland event socket and forwards parsed events.
+///
+/// The returned channel is closed when the socket is closed by Hyprland.
+pub async fn subscribe() -> Result<mpsc::UnboundedReceiver<Event>> {
+    let path = socket_path()?;
+    let stream = UnixStream::connect(&path)
+        .await
+        .with_context(|| format!("Failed to connect to Hyprland event socket: {:?}", path))?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    if let Some(event) = parse_event(&line) {
+                        if tx.send(event).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Ok(None) => {
+                    eprintln!("[Events] Hyprland event socket closed");
+                    break;
+                }
+                Err(e) => {
+                    eprintln!("[Events] Failed to read from event socket: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+/// Forwards every event to `N` new receivers.
+///
+/// Lets several consumers share one event socket connection. Stops when the
+/// source is closed or all receivers are dropped.
+pub fn fan_out<const N: usize>(
+    mut events: mpsc::UnboundedReceiver<Event>,
+) -> [mpsc::UnboundedReceiver<Event>; N] {
+    let mut senders = Vec::with_capacity(N);
+    let receivers = std::array::from_fn(|_| {
+        let (tx, rx) = mpsc::unbounded_channel();
+        senders.push(tx);
+        rx
+    });
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            for tx in &senders {
+                let _ = tx.send(event.clone());
+            }
+            if senders.iter().all(|tx| tx.is_closed()) {
+                break;
+            }
+        }
+    });
+    receivers
+}
@@ -2,15 +2,69 @@
 //! 
 //! This module provides functions and data structures for interacting with
 //! the Hyprland compositor through the hyprctl command-line utility.
+//!
+//! Window management logic goes through the [`HyprlandBackend`] trait so it
+//! can be exercised against an in-memory compositor in tests.
 
+use crate::config::{AppConfig, RestorePolicy};
+use crate::thumbnail::{self, Pixmap, ThumbnailSlot};
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::process::Command;
+use std::sync::{Arc, PoisonError, RwLock};
+use tokio::sync::Notify;
+use tokio::time::{interval, Duration};
 
 /// Represents a Hyprland workspace.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, Default)]
 pub struct Workspace {
     pub id: i32,
+    /// Workspace name, e.g. "3" or "special:spotify"
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Cursor position in global layout coordinates.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CursorPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Information about a monitor in Hyprland.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    /// Position of the top-left corner in layout coordinates
+    pub x: i32,
+    pub y: i32,
+    /// Size in physical pixels
+    pub width: i32,
+    pub height: i32,
+    /// Output scale factor
+    pub scale: f64,
+    /// Output transform (odd values are rotated by 90/270 degrees)
+    #[serde(default)]
+    pub transform: i32,
+    /// Workspace currently shown on this monitor
+    pub active_workspace: Workspace,
+    /// Special workspace shown on top of it (id 0 if none)
+    #[serde(default)]
+    pub special_workspace: Workspace,
+}
+
+impl Monitor {
+    /// Returns true if the point lies within the monitor's logical area.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let scale = if self.scale > 0.0 { self.scale } else { 1.0 };
+        let (mut width, mut height) = (self.width, self.height);
+        if self.transform % 2 == 1 {
+            std::mem::swap(&mut width, &mut height);
+        }
+        let width = (width as f64 / scale).round() as i32;
+        let height = (height as f64 / scale).round() as i32;
+        x >= self.x && x < self.x + width && y >= self.y && y < self.y + height
+    }
 }
 
 /// Information about a window in Hyprland.
@@ -25,6 +79,126 @@ pub struct WindowInfo {
     pub title: String,
     /// Window class (used for matching)
     pub class: String,
+    /// Position of the top-left corner in layout coordinates
+    #[serde(default)]
+    pub at: [i32; 2],
+    /// Size in layout coordinates
+    #[serde(default)]
+    pub size: [i32; 2],
+    /// Whether the window floats (dialogs, picture-in-picture, ...)
+    #[serde(default)]
+    pub floating: bool,
+}
+
+/// The window managed by the daemon, shared between its tasks.
+///
+/// `workspace` is the workspace the window was on when the daemon started,
+/// used by "Restore to workspace". The address and title change when a
+/// duplicate window is adopted (see `singleton_policy`).
+#[derive(Debug, Clone)]
+pub struct ManagedWindow {
+    window: Arc<RwLock<WindowInfo>>,
+    changed: Arc<Notify>,
+}
+
+impl ManagedWindow {
+    /// Starts managing `window`.
+    pub fn new(window: WindowInfo) -> Self {
+        Self {
+            window: Arc::new(RwLock::new(window)),
+            changed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Returns a snapshot of the managed window.
+    ///
+    /// Never panics, so the panic hook can still restore the window after a
+    /// panic during [`ManagedWindow::adopt`] poisoned the lock.
+    pub fn get(&self) -> WindowInfo {
+        self.window.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Returns the address of the managed window.
+    pub fn address(&self) -> String {
+        self.window.read().unwrap_or_else(PoisonError::into_inner).address.clone()
+    }
+
+    /// Switches to another window of the app, keeping the original workspace.
+    pub fn adopt(&self, address: &str, title: &str) {
+        let mut window = self.window.write().unwrap_or_else(PoisonError::into_inner);
+        window.address = address.to_string();
+        window.title = title.to_string();
+        self.changed.notify_one();
+    }
+
+    /// Waits until another window is adopted.
+    pub async fn changed(&self) {
+        self.changed.notified().await
+    }
+}
+
+/// Compositor operations used by the window management logic.
+pub trait HyprlandBackend: Send + Sync {
+    /// Returns all client windows.
+    fn clients(&self) -> Result<Vec<WindowInfo>>;
+    /// Returns the workspace that currently has keyboard focus.
+    fn active_workspace(&self) -> Result<Workspace>;
+    /// Returns the address of the focused window, if any.
+    fn active_window_address(&self) -> Result<Option<String>>;
+    /// Executes a dispatcher command.
+    fn dispatch(&self, command: &str) -> Result<()>;
+    /// Returns the current cursor position.
+    fn cursor_pos(&self) -> Result<CursorPos>;
+    /// Returns all monitors.
+    fn monitors(&self) -> Result<Vec<Monitor>>;
+    /// Captures a scaled-down screenshot of a visible window.
+    fn capture(&self, window: &WindowInfo) -> Result<Pixmap>;
+}
+
+/// Backend talking to the running compositor through `hyprctl`.
+pub struct Hyprctl;
+
+impl HyprlandBackend for Hyprctl {
+    fn clients(&self) -> Result<Vec<WindowInfo>> {
+        hyprctl("clients").context("Failed to get client list")
+    }
+
+    fn active_workspace(&self) -> Result<Workspace> {
+        hyprctl("activeworkspace")
+    }
+
+    fn active_window_address(&self) -> Result<Option<String>> {
+        // hyprctl prints an empty object when no window is focused
+        let window: serde_json::Value = hyprctl("activewindow")?;
+        Ok(window.get("address").and_then(|a| a.as_str()).map(str::to_string))
+    }
+
+    fn dispatch(&self, command: &str) -> Result<()> {
+        dispatch(command)
+    }
+
+    fn cursor_pos(&self) -> Result<CursorPos> {
+        hyprctl("cursorpos")
+    }
+
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        hyprctl("monitors")
+    }
+
+    fn capture(&self, window: &WindowInfo) -> Result<Pixmap> {
+        thumbnail::capture(window)
+    }
+}
+
+/// Outcome of a window toggle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToggleAction {
+    /// The window was moved to its special workspace
+    Minimized,
+    /// The window was brought to the active workspace
+    Restored,
+    /// No matching window was found
+    NotFound,
 }
 
 /// Executes a hyprctl command and returns the parsed JSON output.
@@ -58,53 +232,327 @@ pub fn dispatch(command: &str) -> Result<()> {
     Ok(())
 }
 
-/// Toggles a special workspace and brings it to the front.
-pub fn toggle_special_workspace(class: &str) -> Result<()> {
-    dispatch(&format!("togglespecialworkspace {}", class))?;
-    dispatch("centerwindow")?;
-    dispatch("movetoworkspace +0")?;
-    dispatch("alterzorder top")
+/// Toggles a special workspace, focusing the window hidden in it.
+pub fn toggle_special_workspace(backend: &dyn HyprlandBackend, name: &str) -> Result<()> {
+    backend.dispatch(&format!("togglespecialworkspace {}", name))
+}
+
+/// Runs the app's `restore_dispatch` commands on a window restored to `workspace_id`.
+///
+/// Callers move the window out of its workspace beforehand, so the list only
+/// holds extra dispatchers. The window is focused first, so dispatchers without a window argument
+/// (`centerwindow`, `resizeactive`) act on it rather than on whatever had
+/// focus. `{address}`, `{class}` and `{workspace}` are replaced with the
+/// window's address, its class and the target workspace id.
+pub fn run_restore_dispatch(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    window: &WindowInfo,
+    workspace_id: i32,
+) -> Result<()> {
+    backend.dispatch(&format!("focuswindow address:{}", window.address))?;
+    for template in app_config.restore_dispatch() {
+        let command = template
+            .replace("{address}", &window.address)
+            .replace("{class}", &window.class)
+            .replace("{workspace}", &workspace_id.to_string());
+        backend.dispatch(&command)?;
+    }
+    Ok(())
+}
+
+/// Returns the companion windows of the app's primary window.
+pub fn find_companions<'a>(
+    app_config: &AppConfig,
+    clients: &'a [WindowInfo],
+    primary_address: &str,
+) -> Vec<&'a WindowInfo> {
+    clients
+        .iter()
+        .filter(|c| c.address != primary_address && app_config.is_companion(&c.class, &c.title))
+        .collect()
+}
+
+/// Returns windows on the same workspace as `window` that do not belong to the app.
+///
+/// Used to detect special workspaces shared with other apps, where toggling the
+/// whole workspace would briefly reveal their hidden windows.
+pub fn foreign_windows<'a>(
+    app_config: &AppConfig,
+    clients: &'a [WindowInfo],
+    window: &WindowInfo,
+) -> Vec<&'a WindowInfo> {
+    clients
+        .iter()
+        .filter(|c| {
+            c.address != window.address
+                && c.workspace.id == window.workspace.id
+                && c.workspace.name == window.workspace.name
+                && !app_config.is_companion(&c.class, &c.title)
+        })
+        .collect()
+}
+
+/// Returns true if `window` can be taken over as the app's window.
+///
+/// Windows of the app's class hidden in another special workspace, or managed
+/// by another daemon (`held`), belong to other apps sharing the class.
+pub fn is_claimable(app_config: &AppConfig, window: &WindowInfo, held: &[String]) -> bool {
+    let special = format!("special:{}", app_config.special_workspace());
+    window.class == app_config.class
+        && (window.workspace.id >= 0 || window.workspace.name == special)
+        && !held.contains(&window.address)
+}
+
+/// Finds a window the app can take over when it starts.
+///
+/// Prefers a window already hidden in the app's own special workspace.
+pub fn find_app_window<'a>(
+    app_config: &AppConfig,
+    clients: &'a [WindowInfo],
+    held: &[String],
+) -> Option<&'a WindowInfo> {
+    let special = format!("special:{}", app_config.special_workspace());
+    let mut claimable = clients.iter().filter(|c| is_claimable(app_config, c, held));
+    claimable.clone().find(|c| c.workspace.name == special).or_else(|| claimable.next())
+}
+
+/// Silently moves the app's companion windows to `target` (e.g. "3" or "special:name").
+pub fn move_companions(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    clients: &[WindowInfo],
+    primary_address: &str,
+    target: &str,
+) -> Result<()> {
+    for companion in find_companions(app_config, clients, primary_address) {
+        backend.dispatch(&format!(
+            "movetoworkspacesilent {},address:{}",
+            target, companion.address
+        ))?;
+    }
+    Ok(())
+}
+
+/// Closes a window together with its companion windows.
+pub fn close_window_group(backend: &dyn HyprlandBackend, app_config: &AppConfig, address: &str) -> Result<()> {
+    let clients = backend.clients()?;
+    for companion in find_companions(app_config, &clients, address) {
+        backend.dispatch(&format!("closewindow address:{}", companion.address))?;
+    }
+    backend.dispatch(&format!("closewindow address:{}", address))
+}
+
+/// Stores a thumbnail of the window before it is minimized, if enabled for the app.
+///
+/// The screenshot is taken from the screen area the window occupies, so it is
+/// only captured while the window's workspace is shown on a monitor and no
+/// other window or special workspace may cover that area. Popups and menus
+/// are not Hyprland clients and can still end up in the preview.
+pub fn capture_thumbnail(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    window: &WindowInfo,
+    thumbnails: &ThumbnailSlot,
+) {
+    if !app_config.tooltip_thumbnail.unwrap_or(false) {
+        return;
+    }
+    let monitors = backend.monitors().unwrap_or_default();
+    let Some(monitor) = monitors.iter().find(|m| m.active_workspace.id == window.workspace.id) else {
+        return;
+    };
+    if monitor.special_workspace.id != 0 {
+        println!("[Thumbnail] Skipping capture: a special workspace covers the window");
+        return;
+    }
+    let clients = backend.clients().unwrap_or_default();
+    if let Some(other) = clients.iter().find(|c| {
+        c.address != window.address && c.workspace.id == window.workspace.id && overlaps(c, window)
+    }) {
+        println!("[Thumbnail] Skipping capture: window may be covered by '{}'", other.title);
+        return;
+    }
+    match backend.capture(window) {
+        Ok(pixmap) => thumbnails.set(pixmap),
+        Err(e) => eprintln!("[Thumbnail] Failed to capture window: {}", e),
+    }
+}
+
+/// Returns true if the on-screen areas of two windows intersect.
+fn overlaps(a: &WindowInfo, b: &WindowInfo) -> bool {
+    let apart = |a: &WindowInfo, b: &WindowInfo, axis: usize| a.at[axis] + a.size[axis] <= b.at[axis];
+    !(0..2).any(|axis| apart(a, b, axis) || apart(b, a, axis))
+}
+
+/// Moves a window and its companions to the special workspace without changing focus.
+///
+/// Returns `Ok(false)` if the window no longer exists or is already minimized.
+pub fn minimize_window(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    address: &str,
+    thumbnails: &ThumbnailSlot,
+) -> Result<bool> {
+    let clients = backend.clients()?;
+    let special = format!("special:{}", app_config.special_workspace());
+
+    match clients.iter().find(|c| c.address == address) {
+        Some(window) if window.workspace.id >= 0 => {
+            capture_thumbnail(backend, app_config, window, thumbnails);
+            backend.dispatch(&format!("movetoworkspacesilent {},address:{}", special, address))?;
+            move_companions(backend, app_config, &clients, address, &special)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Returns the workspace shown on the monitor under the mouse cursor.
+///
+/// Falls back to the keyboard-focused workspace if no monitor contains the cursor.
+pub fn workspace_under_cursor(backend: &dyn HyprlandBackend) -> Result<Workspace> {
+    let cursor = backend.cursor_pos()?;
+    let monitors = backend.monitors()?;
+    match monitors.into_iter().find(|m| m.contains(cursor.x, cursor.y)) {
+        Some(monitor) => Ok(monitor.active_workspace),
+        None => backend.active_workspace(),
+    }
+}
+
+/// Moves a window directly to a workspace, focuses it and runs the restore dispatchers.
+fn restore_to_workspace(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    window: &WindowInfo,
+    workspace_id: i32,
+) -> Result<()> {
+    backend.dispatch(&format!("movetoworkspace {},address:{}", workspace_id, window.address))?;
+    run_restore_dispatch(backend, app_config, window, workspace_id)
 }
 
 /// Handles window toggling between workspaces based on current state.
 /// 
+/// The managed window is looked up by `address`, falling back to the first
+/// window of the app's class if it is gone. Windows another app sharing the
+/// class has hidden or taken over (`held`) are never picked up this way.
+///
 /// This function implements the core window management logic:
 /// - If in special workspace: move to active workspace
 /// - If in current workspace: move to special workspace
 /// - If in different workspace: move to current workspace
-pub async fn handle_window_toggle(workspace_name: &str) -> Result<()> {
-    let clients: Vec<WindowInfo> = hyprctl("clients")
-        .context("Failed to get client list")?;
+///
+/// With [`RestorePolicy::FollowCursor`] the "current" workspace is the one
+/// shown on the monitor under the mouse cursor instead of the focused one.
+/// Companion windows always follow the primary window.
+/// If the special workspace also holds other apps' windows, the window is
+/// moved out directly instead of toggling the workspace, so they stay hidden.
+/// Every restore ends with the app's `restore_dispatch` commands.
+/// A thumbnail is captured right before minimizing and cleared on restore.
+pub async fn handle_window_toggle(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    address: &str,
+    held: &[String],
+    thumbnails: &ThumbnailSlot,
+) -> Result<ToggleAction> {
+    let class = app_config.class.as_str();
+    let special = format!("special:{}", app_config.special_workspace());
+    let clients = backend.clients()?;
     
-    let window = match clients.iter().find(|c| c.class == workspace_name) {
+    // Prefer a window that is not itself a companion sharing the primary class
+    let mut claimable = clients.iter().filter(|c| is_claimable(app_config, c, held));
+    let primary = clients
+        .iter()
+        .find(|c| c.address == address && c.class == class)
+        .or_else(|| claimable.clone().find(|c| !app_config.is_companion(&c.class, &c.title)))
+        .or_else(|| claimable.next());
+    let window = match primary {
         Some(w) => w,
         None => {
             println!("[Toggle] Window not found, ignoring signal");
-            return Ok(());
+            return Ok(ToggleAction::NotFound);
         }
     };
     
-    let current_workspace = hyprctl::<Workspace>("activeworkspace")?;
+    let follow_cursor = app_config.restore_policy == RestorePolicy::FollowCursor;
+    let current_workspace = if follow_cursor {
+        workspace_under_cursor(backend)?
+    } else {
+        backend.active_workspace()?
+    };
+    let current_target = current_workspace.id.to_string();
     
-    if window.workspace.id < 0 {
+    if window.workspace.id < 0 && follow_cursor {
+        // Window is in special workspace, move to the workspace under the cursor
+        println!("[Toggle] Moving from special workspace to workspace {} under cursor", current_workspace.id);
+        move_companions(backend, app_config, &clients, &window.address, &current_target)?;
+        restore_to_workspace(backend, app_config, window, current_workspace.id)?;
+        thumbnails.clear();
+        Ok(ToggleAction::Restored)
+    } else if window.workspace.id < 0 && !foreign_windows(app_config, &clients, window).is_empty() {
+        // Special workspace is shared with other apps, only bring this window out
+        println!("[Toggle] Special workspace '{}' is shared, moving window out directly", window.workspace.name);
+        move_companions(backend, app_config, &clients, &window.address, &current_target)?;
+        restore_to_workspace(backend, app_config, window, current_workspace.id)?;
+        thumbnails.clear();
+        Ok(ToggleAction::Restored)
+    } else if window.workspace.id < 0 {
         // Window is in special workspace, move to active workspace
         println!("[Toggle] Moving from special workspace to active");
-        toggle_special_workspace(workspace_name)?;
+        move_companions(backend, app_config, &clients, &window.address, &current_target)?;
+        let name = window.workspace.name.strip_prefix("special:").unwrap_or(app_config.special_workspace());
+        toggle_special_workspace(backend, name)?;
+        backend.dispatch(&format!("movetoworkspace +0,address:{}", window.address))?;
+        run_restore_dispatch(backend, app_config, window, current_workspace.id)?;
+        thumbnails.clear();
+        Ok(ToggleAction::Restored)
     } else if window.workspace.id == current_workspace.id {
         // Window is in current workspace, move to special workspace
         println!("[Toggle] Moving from current workspace to special");
-        dispatch(&format!("focuswindow initialclass:{}", workspace_name))?;
-        dispatch(&format!(
-            "movetoworkspacesilent special:{},address:{}",
-            workspace_name, window.address
+        capture_thumbnail(backend, app_config, window, thumbnails);
+        backend.dispatch(&format!("focuswindow address:{}", window.address))?;
+        backend.dispatch(&format!(
+            "movetoworkspacesilent {},address:{}",
+            special, window.address
         ))?;
+        move_companions(backend, app_config, &clients, &window.address, &special)?;
+        Ok(ToggleAction::Minimized)
+    } else if follow_cursor {
+        // Window is in different workspace, move to the workspace under the cursor
+        println!("[Toggle] Moving from workspace {} to {} under cursor", window.workspace.id, current_workspace.id);
+        move_companions(backend, app_config, &clients, &window.address, &current_target)?;
+        restore_to_workspace(backend, app_config, window, current_workspace.id)?;
+        Ok(ToggleAction::Restored)
     } else {
         // Window is in different workspace, move to current
         println!("[Toggle] Moving from workspace {} to current", window.workspace.id);
-        dispatch(&format!("movetoworkspace +0,address:{}", window.address))?;
-        dispatch("centerwindow")?;
-        dispatch("alterzorder top")?;
+        move_companions(backend, app_config, &clients, &window.address, &current_target)?;
+        backend.dispatch(&format!("movetoworkspace +0,address:{}", window.address))?;
+        run_restore_dispatch(backend, app_config, window, current_workspace.id)?;
+        Ok(ToggleAction::Restored)
+    }
+}
+
+/// Polls the client list until the managed window disappears.
+///
+/// Calls `on_update` with the window on every check while it still exists.
+/// Follows the window across adoptions.
+/// Returns `Ok(())` once the window is closed, or the error that stopped polling.
+pub async fn watch_window(
+    backend: &dyn HyprlandBackend,
+    window: &ManagedWindow,
+    period: Duration,
+    mut on_update: impl FnMut(&WindowInfo),
+) -> Result<()> {
+    let mut check_interval = interval(period);
+    loop {
+        check_interval.tick().await;
+        let clients = backend.clients()?;
+        let address = window.address();
+        match clients.iter().find(|c| c.address == address) {
+            Some(window) => on_update(window),
+            None => return Ok(()),
+        }
     }
-    
-    Ok(())
 }
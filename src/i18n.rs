@@ -0,0 +1,138 @@
+//! Localization of user-visible menu labels.
+//!
+//! Labels are looked up from a small set of bundled translations keyed by the
+//! language of the current locale (`LANGUAGE`, then `LC_ALL`, `LC_MESSAGES` or
+//! `LANG`, as gettext does), and can be overridden from the `[labels]` section
+//! of the config file.
+//!
+//! Labels are templates: `{title}` is replaced with the window title and
+//! `{workspace}` with the original workspace id.
+
+use serde::Deserialize;
+
+/// Label templates overridden in the config file.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct LabelOverrides {
+    /// Label for the "Toggle" menu item
+    pub toggle: Option<String>,
+    /// Label for the "Restore to workspace" menu item
+    pub restore: Option<String>,
+    /// Label for the "Close" menu item
+    pub close: Option<String>,
+}
+
+/// Resolved label templates for the tray menu.
+#[derive(Debug, Clone)]
+pub struct Labels {
+    toggle: String,
+    restore: String,
+    close: String,
+}
+
+/// Bundled translations: (language, toggle, restore, close).
+const TRANSLATIONS: &[(&str, &str, &str, &str)] = &[
+    ("en", "Toggle {title}", "Restore to workspace ({workspace})", "Close {title}"),
+    ("it", "Mostra/nascondi {title}", "Ripristina nello spazio di lavoro ({workspace})", "Chiudi {title}"),
+    ("de", "{title} ein-/ausblenden", "Auf Arbeitsbereich wiederherstellen ({workspace})", "{title} schließen"),
+    ("fr", "Afficher/masquer {title}", "Restaurer sur l'espace de travail ({workspace})", "Fermer {title}"),
+    ("es", "Mostrar/ocultar {title}", "Restaurar al espacio de trabajo ({workspace})", "Cerrar {title}"),
+    ("pt", "Mostrar/ocultar {title}", "Restaurar para a área de trabalho ({workspace})", "Fechar {title}"),
+];
+
+impl Labels {
+    /// Resolves labels for the current locale, applying config overrides.
+    pub fn load(overrides: &LabelOverrides) -> Self {
+        Self::for_language(&current_language(), overrides)
+    }
+
+    /// Resolves labels for a language code (e.g. "de"), falling back to English.
+    pub fn for_language(language: &str, overrides: &LabelOverrides) -> Self {
+        let (_, toggle, restore, close) = TRANSLATIONS
+            .iter()
+            .find(|(lang, ..)| *lang == language)
+            .unwrap_or(&TRANSLATIONS[0]);
+
+        Self {
+            toggle: overrides.toggle.clone().unwrap_or_else(|| toggle.to_string()),
+            restore: overrides.restore.clone().unwrap_or_else(|| restore.to_string()),
+            close: overrides.close.clone().unwrap_or_else(|| close.to_string()),
+        }
+    }
+
+    /// Label for the "Toggle" menu item.
+    pub fn toggle(&self, title: &str) -> String {
+        render(&self.toggle, title, 0)
+    }
+
+    /// Label for the "Restore to workspace" menu item.
+    pub fn restore(&self, title: &str, workspace: i32) -> String {
+        render(&self.restore, title, workspace)
+    }
+
+    /// Label for the "Close" menu item.
+    pub fn close(&self, title: &str) -> String {
+        render(&self.close, title, 0)
+    }
+}
+
+/// Returns the language code of the current locale (e.g. "it" for "it_IT.UTF-8").
+fn current_language() -> String {
+    detect_language(|var| std::env::var(var).ok())
+}
+
+/// Picks the message language from locale variables read through `var`.
+///
+/// Like gettext, the first entry of the colon-separated `LANGUAGE` list with a
+/// bundled translation wins, unless the locale is "C" or "POSIX".
+pub fn detect_language(var: impl Fn(&str) -> Option<String>) -> String {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| var(name))
+        .find(|value| !value.is_empty());
+    let locale_language = locale.as_deref().map(language_code);
+
+    if !matches!(locale.as_deref(), Some("C" | "POSIX")) {
+        let preferred = var("LANGUAGE")
+            .unwrap_or_default()
+            .split(':')
+            .map(language_code)
+            .find(|lang| TRANSLATIONS.iter().any(|(l, ..)| l == lang));
+        if let Some(language) = preferred {
+            return language;
+        }
+    }
+    locale_language.unwrap_or_else(|| "en".to_string())
+}
+
+/// Extracts the language code from a locale name.
+fn language_code(locale: &str) -> String {
+    locale
+        .split(['_', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Substitutes placeholders in a label template.
+///
+/// Done in a single pass, so placeholders inside the title are left alone.
+pub fn render(template: &str, title: &str, workspace: i32) -> String {
+    let mut rendered = String::with_capacity(template.len() + title.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(tail) = rest.strip_prefix("{title}") {
+            rendered.push_str(title);
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("{workspace}") {
+            rendered.push_str(&workspace.to_string());
+            rest = tail;
+        } else {
+            rendered.push('{');
+            rest = &rest[1..];
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
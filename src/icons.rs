@@ -0,0 +1,65 @@
+//! Custom icon directory lookup.
+//!
+//! Users can ship icons outside the system theme by listing directories in
+//! the `icon_dirs` config option. Each directory may contain icon files
+//! directly (`my-icons/app.svg`) or follow the theme layout
+//! (`my-icons/hicolor/48x48/apps/app.png`). The directory holding the icon
+//! is advertised to trays through the SNI `IconThemePath` property.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File extensions trays can load icons from.
+const ICON_EXTENSIONS: &[&str] = &["svg", "png", "xpm"];
+
+/// Maximum directory depth searched below each icon directory.
+const MAX_SEARCH_DEPTH: usize = 4;
+
+/// Expands a leading `~` to the user's home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(format!("{}{}", home, rest))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Returns the icon directory to advertise as `IconThemePath`.
+///
+/// Prefers the first directory that contains the icon, falling back to the
+/// first existing directory so trays can still search it.
+pub fn icon_theme_path(icon_dirs: &[String], icon: &str) -> Option<PathBuf> {
+    let dirs: Vec<PathBuf> = icon_dirs
+        .iter()
+        .map(|d| expand_home(d))
+        .filter(|d| d.is_dir())
+        .collect();
+
+    dirs.iter()
+        .find(|dir| contains_icon(dir, icon, MAX_SEARCH_DEPTH))
+        .or_else(|| dirs.first())
+        .cloned()
+}
+
+/// Returns true if `dir` or one of its subdirectories holds an icon file named `icon`.
+fn contains_icon(dir: &Path, icon: &str, depth: usize) -> bool {
+    if ICON_EXTENSIONS
+        .iter()
+        .any(|ext| dir.join(format!("{}.{}", icon, ext)).is_file())
+    {
+        return true;
+    }
+    if depth == 0 {
+        return false;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .any(|path| contains_icon(&path, icon, depth - 1))
+}
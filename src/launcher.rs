@@ -1,11 +1,17 @@
 //! Application launcher module.
 //! 
-//! This module handles launching configured applications and sending
-//! desktop notifications when applications start.
+//! This module handles launching configured applications, sending
+//! desktop notifications when applications start and waiting for their
+//! windows to appear.
 
 use crate::config::AppConfig;
+use crate::hyprland::{self, HyprlandBackend, WindowInfo};
 use anyhow::{Context, Result};
 use std::process::Command;
+use tokio::time::Duration;
+
+/// Interval between checks while waiting for a launched window.
+pub const LAUNCH_POLL_INTERVAL_MS: u64 = 500;
 
 /// Launches an application based on its configuration.
 /// 
@@ -22,9 +28,9 @@ pub fn launch_application(app_config: &AppConfig) -> Result<()> {
     
     // Send notification if notify_name is specified
     if let Some(notify_name) = &app_config.notify_name {
-        let icon = app_config.icon.as_deref().unwrap_or(&app_config.class);
+        let icon = app_config.icon_name();
         let _ = Command::new("notify-send")
-            .args(&["-a", notify_name, "Launched", "-i", icon, "-r", "2590", "-u", "low"])
+            .args(["-a", notify_name, "Launched", "-i", icon, "-r", "2590", "-u", "low"])
             .spawn();
     }
 
@@ -39,3 +45,43 @@ pub fn launch_application(app_config: &AppConfig) -> Result<()> {
 
     Ok(())
 }
+
+/// Waits for a window of the app's class to appear.
+///
+/// Only windows the app can take over count, see [`hyprland::is_claimable`].
+/// Polls the client list every [`LAUNCH_POLL_INTERVAL_MS`] for at least
+/// `timeout_secs` seconds (and at least 10 attempts). Transient errors from
+/// the backend are ignored while waiting.
+///
+/// # Returns
+/// * `Some(window)` as soon as a matching window is found
+/// * `None` if no window appeared before the timeout
+pub async fn wait_for_window(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    held: &[String],
+    timeout_secs: u64,
+) -> Option<WindowInfo> {
+    let class = &app_config.class;
+    let max_attempts = (timeout_secs * 2).max(10) as usize; // Check every ~500ms
+
+    println!("[Launch] Waiting up to {} seconds for '{}' to appear...", timeout_secs, class);
+
+    for attempt in 1..=max_attempts {
+        tokio::time::sleep(Duration::from_millis(LAUNCH_POLL_INTERVAL_MS)).await;
+
+        if let Ok(clients) = backend.clients() {
+            if let Some(window) = clients.into_iter().find(|c| hyprland::is_claimable(app_config, c, held)) {
+                println!("[Launch] Found window after {:.1}s (attempt {})", attempt as f64 * 0.5, attempt);
+                return Some(window);
+            }
+        }
+
+        // Show progress for slow launches
+        if attempt % 4 == 0 {
+            println!("[Launch] Still waiting... ({}s elapsed)", attempt / 2);
+        }
+    }
+
+    None
+}
@@ -0,0 +1,21 @@
+//! Hyprland Minimizer - A minimize-to-tray utility for Hyprland.
+//!
+//! The library exposes the daemon's building blocks so the window management
+//! logic can be tested against a fake [`hyprland::HyprlandBackend`].
+
+pub mod auto_minimize;
+pub mod autostart;
+pub mod cleanup;
+pub mod config;
+pub mod dbus;
+pub mod events;
+pub mod hyprland;
+pub mod i18n;
+pub mod icons;
+pub mod launcher;
+pub mod lock;
+pub mod portal;
+pub mod rules;
+pub mod singleton;
+pub mod state;
+pub mod thumbnail;
@@ -8,13 +8,18 @@ use anyhow::{Context, Result};
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Stdio};
+
+/// Returns the directory for per-session runtime files (lock and state files).
+pub fn runtime_dir() -> PathBuf {
+    std::env::var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("/tmp"))
+}
 
 /// Returns the path to the lock file for a given application.
 fn get_lock_file_path(app_name: &str) -> PathBuf {
-    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
-        .unwrap_or_else(|_| "/tmp".to_string());
-    PathBuf::from(runtime_dir).join(format!("hyprland-minimizer-{}.pid", app_name))
+    runtime_dir().join(format!("hyprland-minimizer-{}.pid", app_name))
 }
 
 /// Acquires an exclusive lock for the application.
@@ -35,12 +40,7 @@ pub fn acquire_lock(app_name: &str) -> Result<Option<i32>> {
         if let Ok(old_pid_str) = fs::read_to_string(&lock_file) {
             if let Ok(old_pid) = old_pid_str.trim().parse::<i32>() {
                 // Check if the process is actually running
-                let check_result = Command::new("kill")
-                    .arg("-0")  // Signal 0 just checks if process exists
-                    .arg(old_pid.to_string())
-                    .status();
-                
-                if check_result.is_ok() && check_result.unwrap().success() {
+                if is_running(old_pid) {
                     println!("[Lock] Found running daemon with PID {}. Sending toggle signal...", old_pid);
                     // Send SIGUSR1 signal to toggle the window
                     let _ = Command::new("kill")
@@ -67,6 +67,20 @@ pub fn acquire_lock(app_name: &str) -> Result<Option<i32>> {
     Ok(None)
 }
 
+/// Returns whether a process with the given PID exists.
+pub fn is_running(pid: i32) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+    Command::new("kill")
+        .arg("-0")  // Signal 0 just checks if process exists
+        .arg(pid.to_string())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
 /// Releases the lock file when the application exits.
 /// 
 /// Only removes the lock file if it contains the current process's PID,
@@ -3,36 +3,66 @@
 //! This application creates system tray icons for Hyprland windows and allows
 //! toggling them between workspaces and a special "minimized" workspace.
 
-mod config;
-mod dbus;
-mod hyprland;
-mod launcher;
-mod lock;
-
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::signal::unix::{signal, SignalKind};
+use tokio::signal::unix::{signal, Signal, SignalKind};
 use tokio::sync::Notify;
-use tokio::time::{interval, Duration};
-use tokio_stream::StreamExt;
-use zbus::ConnectionBuilder;
-
-use config::Config;
-use dbus::{DbusMenu, StatusNotifierItem, DBUS_WATCHER_NAME, REREGISTER_DELAY_MS};
-use hyprland::WindowInfo;
+use tokio::time::Duration;
+
+use hyprland_minimizer::autostart::{self, AutostartTarget};
+use hyprland_minimizer::cleanup::Cleanup;
+use hyprland_minimizer::config::Config;
+use hyprland_minimizer::dbus::{self, BusAddress, MenuModel, StatusNotifierItem};
+use hyprland_minimizer::hyprland::{self, HyprlandBackend, Hyprctl, ManagedWindow};
+use hyprland_minimizer::i18n::Labels;
+use hyprland_minimizer::state::{self, DaemonState, Stats};
+use hyprland_minimizer::thumbnail::ThumbnailSlot;
+use hyprland_minimizer::{auto_minimize, events, icons, launcher, lock, portal, rules, singleton};
 
 /// Interval for checking if the managed window still exists.
 const WINDOW_CHECK_INTERVAL_SECS: u64 = 2;
 
 /// Command-line arguments parser.
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     /// The workspace/app identifier (e.g., whatsapp, spotify)
     app_name: Option<String>,
 }
 
+/// Auxiliary subcommands (the default mode runs the daemon for `app_name`).
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Start apps with `launch_in_background = true` at login
+    InstallAutostart {
+        /// Install as XDG autostart entries or as hyprland.conf exec-once lines
+        #[arg(long, value_enum, default_value_t = AutostartTarget::Desktop)]
+        target: AutostartTarget,
+
+        /// Hyprland config file to edit (default: ~/.config/hypr/hyprland.conf)
+        #[arg(long)]
+        hyprland_conf: Option<PathBuf>,
+
+        /// Apps to install (default: every app with launch_in_background = true)
+        apps: Vec<String>,
+    },
+    /// Show usage statistics of running daemons
+    Status {
+        /// Apps to report on (default: all configured apps)
+        apps: Vec<String>,
+    },
+    /// Print suggested Hyprland windowrulev2 lines for configured apps
+    GenRules {
+        /// Apps to generate rules for (default: all configured apps)
+        apps: Vec<String>,
+    },
+}
+
 // --- Main Application Logic ---
 
 #[tokio::main(flavor = "current_thread")]
@@ -42,6 +72,23 @@ async fn main() -> Result<()> {
     // 1. Load configuration
     let config = Config::load()?;
 
+    if let Some(command) = args.command {
+        return match command {
+            Commands::InstallAutostart { target, hyprland_conf, apps } => {
+                autostart::install(&config, &apps, target, hyprland_conf.as_deref())
+            }
+            Commands::Status { apps } => {
+                let names: Vec<&str> = config.select_apps(&apps)?.into_iter().map(|(name, _)| name).collect();
+                state::print_status(&names)
+            }
+            Commands::GenRules { apps } => {
+                let selected = config.select_apps(&apps)?;
+                print!("{}", rules::generate(&selected));
+                Ok(())
+            }
+        };
+    }
+
     // 2. Validate app name parameter
     let app_name = match args.app_name {
         Some(name) if config.apps.contains_key(&name) => name,
@@ -67,40 +114,39 @@ async fn main() -> Result<()> {
         std::process::exit(0);
     }
 
+    // Catch stop signals from here on: their default action would kill the
+    // daemon without applying the exit policy or releasing the lock
+    let mut sigterm = signal(SignalKind::terminate())
+        .context("Failed to create SIGTERM handler")?;
+    let mut sigint = signal(SignalKind::interrupt())
+        .context("Failed to create SIGINT handler")?;
+
     // 4. Find or launch the application
-    let clients: Vec<WindowInfo> = hyprland::hyprctl("clients")
+    let backend: Arc<dyn HyprlandBackend> = Arc::new(Hyprctl);
+    let clients = backend.clients()
         .context("Failed to get client list from Hyprland.")?;
-    let (mut window_info, is_newly_launched) = match clients.into_iter().find(|c| c.class == app_config.class) {
-        Some(window) => (window, false),
+    // Leave alone windows of a shared class that other apps have hidden or taken over
+    let held = state::held_addresses(&app_name);
+    let existing = hyprland::find_app_window(&app_config, &clients, &held).cloned();
+    let (mut window_info, launch_duration) = match existing {
+        Some(window) => (window, None),
         None => {
+            let launch_started = std::time::Instant::now();
             launcher::launch_application(&app_config)?;
             
             // Wait for the application to appear with retry mechanism
             let timeout_secs = app_config.launch_timeout.unwrap_or(10);
-            let max_attempts = (timeout_secs * 2).max(10) as usize; // Check every ~500ms
-            let mut found_window = None;
-            
-            println!("[Launch] Waiting up to {} seconds for '{}' to appear...", timeout_secs, app_config.class);
-            
-            for attempt in 1..=max_attempts {
-                tokio::time::sleep(Duration::from_millis(500)).await;
-                
-                if let Ok(clients) = hyprland::hyprctl::<Vec<WindowInfo>>("clients") {
-                    if let Some(window) = clients.into_iter().find(|c| c.class == app_config.class) {
-                        println!("[Launch] Found window after {:.1}s (attempt {})", attempt as f64 * 0.5, attempt);
-                        found_window = Some(window);
-                        break;
-                    }
-                }
-                
-                // Show progress for slow launches
-                if attempt % 4 == 0 {
-                    println!("[Launch] Still waiting... ({}s elapsed)", attempt / 2);
+            let found_window = tokio::select! {
+                window = launcher::wait_for_window(&*backend, &app_config, &held, timeout_secs) => window,
+                name = stop_signal(&mut sigterm, &mut sigint) => {
+                    println!("[Daemon] Received {} while waiting for the app to launch.", name);
+                    lock::release_lock(&app_name);
+                    return Ok(());
                 }
-            }
+            };
             
             match found_window {
-                Some(w) => (w, true),
+                Some(w) => (w, Some(launch_started.elapsed())),
                 None => {
                     eprintln!("[Error] Failed to find window with class '{}' after {} seconds", 
                               app_config.class, timeout_secs);
@@ -122,24 +168,50 @@ async fn main() -> Result<()> {
         window_info.class = app_config.class.clone();
     }
 
-    // Wrap in Arc for sharing without cloning the struct
-    let window_info = Arc::new(window_info);
+    let is_newly_launched = launch_duration.is_some();
+    let state = Arc::new(DaemonState::new(&app_name, Stats {
+        pid: std::process::id(),
+        class: window_info.class.clone(),
+        address: window_info.address.clone(),
+        title: window_info.title.clone(),
+        started_at: state::now_secs(),
+        minimized_since: (window_info.workspace.id < 0).then(state::now_secs),
+        launch_duration_ms: launch_duration.map(|d| d.as_millis() as u64),
+        ..Default::default()
+    }));
+
+    // Shared between tasks; the address changes if a duplicate window is adopted
+    let window_info = ManagedWindow::new(window_info);
+    let thumbnails = ThumbnailSlot::default();
+
+    // Don't strand the window in the special workspace if the daemon dies unexpectedly
+    let cleanup = Cleanup::new(
+        &app_name,
+        app_config.clone(),
+        window_info.clone(),
+        Arc::clone(&state),
+        Arc::clone(&backend),
+    );
+    cleanup.install_panic_hook();
+    let cleanup_guard = cleanup.guard();
 
     // 5. Perform initial toggle if needed
     if !is_newly_launched {
         // App already exists, toggle it
-        let _ = hyprland::handle_window_toggle(&app_config.class).await;
+        state.record_toggle();
+        if let Ok(action) = hyprland::handle_window_toggle(&*backend, &app_config, &window_info.address(), &held, &thumbnails).await {
+            state.record_action(action);
+        }
     } else {
         // App just launched
         if app_config.launch_in_background.unwrap_or(false) {
             // Move to special workspace immediately
             println!("[Daemon] Newly launched - moving to special workspace (background)");
             tokio::time::sleep(Duration::from_millis(500)).await; // Give app time to settle
-            let _ = hyprland::dispatch(&format!("focuswindow address:{}", window_info.address));
-            let _ = hyprland::dispatch(&format!(
-                "movetoworkspacesilent special:{},address:{}",
-                app_config.class, window_info.address
-            ));
+            let address = window_info.address();
+            let _ = backend.dispatch(&format!("focuswindow address:{}", address));
+            let _ = hyprland::minimize_window(&*backend, &app_config, &address, &thumbnails);
+            state.set_minimized(true);
         } else {
             // Keep on current workspace
             println!("[Daemon] Newly launched - keeping window on current workspace");
@@ -149,133 +221,173 @@ async fn main() -> Result<()> {
     // 5. Set up the D-Bus services (always create tray icon)
     let exit_notify = Arc::new(Notify::new());
 
+    let icon = app_config.icon_name();
+    let icon_theme_path = icons::icon_theme_path(&config.icon_dirs, icon)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
     let notifier_item = StatusNotifierItem {
-        window_info: Arc::clone(&window_info),
+        backend: Arc::clone(&backend),
+        app_config: app_config.clone(),
+        window_info: window_info.clone(),
         exit_notify: Arc::clone(&exit_notify),
+        icon_theme_path,
+        thumbnails: thumbnails.clone(),
     };
 
-    let dbus_menu = DbusMenu {
-        window_info: Arc::clone(&window_info),
+    let menu_model = Arc::new(MenuModel {
+        backend: Arc::clone(&backend),
+        app_config: app_config.clone(),
+        window_info: window_info.clone(),
         exit_notify: Arc::clone(&exit_notify),
-    };
+        state: Arc::clone(&state),
+        labels: Labels::load(&config.labels),
+        thumbnails: thumbnails.clone(),
+    });
 
     let bus_name = format!(
         "org.kde.StatusNotifierItem.{}.p{}",
         app_name, std::process::id()
     );
 
-    let connection = ConnectionBuilder::session()?
-        .name(bus_name.as_str())?
-        .serve_at("/StatusNotifierItem", notifier_item)?
-        .serve_at("/Menu", dbus_menu)?
-        .build()
-        .await?;
-
-    // Create an Arc of the connection to share with the watcher task.
-    let arc_conn = Arc::new(connection);
+    let connection = dbus::serve(&BusAddress::Session, &bus_name, notifier_item.clone(), &menu_model).await?;
 
     println!("D-Bus service '{}' is running.", bus_name);
 
     // 6. Initial registration with the StatusNotifierWatcher
-    if let Err(e) = dbus::register_with_watcher(&arc_conn, &bus_name).await {
+    if let Err(e) = dbus::register_with_watcher(&connection, &bus_name).await {
         eprintln!("Could not register with StatusNotifierWatcher: {}", e);
         eprintln!("Is a tray like Waybar running?");
-        let _ = hyprland::dispatch(&format!(
-            "movetoworkspace {},address:{}",
-            window_info.workspace.id, window_info.address
-        ));
+        // The cleanup guard moves the window back when returning here
         anyhow::bail!("Failed to register tray icon.");
     }
     println!("Registration successful.");
 
-    // Task to watch for Waybar restarts and re-register the icon.
-    let conn_clone = Arc::clone(&arc_conn);
-    let bus_name_clone = bus_name.clone();
-    tokio::spawn(async move {
-        let dbus_proxy = match zbus::fdo::DBusProxy::new(&*conn_clone).await {
-            Ok(p) => p,
-            Err(e) => {
-                eprintln!("[Watcher] Failed to connect to D-Bus proxy: {}", e);
-                return;
-            }
-        };
-
-        let mut owner_changes = match dbus_proxy.receive_name_owner_changed().await {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("[Watcher] Failed to listen for owner changes: {}", e);
-                return;
+    // Keep background apps alive under Flatpak sandbox policies
+    if portal::is_sandboxed() && app_config.launch_in_background.unwrap_or(false) {
+        let conn_clone = connection.clone();
+        let reason = format!("Keep {} minimized in the system tray", app_config.name);
+        let commandline = vec!["hyprland-minimizer".to_string(), app_name.clone()];
+        let autostart = app_config.portal_autostart.unwrap_or(false);
+        tokio::spawn(async move {
+            match portal::request_background(&conn_clone, &reason, autostart, &commandline).await {
+                Ok(grant) => println!(
+                    "[Portal] Background permission: {}, autostart: {}",
+                    grant.background, grant.autostart
+                ),
+                Err(e) => eprintln!("[Portal] Failed to request background permission: {}", e),
             }
-        };
-
-        println!("[Watcher] Watching for '{}' restarts...", DBUS_WATCHER_NAME);
+        });
+    }
 
-        while let Some(signal) = owner_changes.next().await {
-            if let Ok(args) = signal.args() {
-                if args.name() == DBUS_WATCHER_NAME && args.new_owner().is_some() {
-                    println!("[Watcher] Tray service detected. Re-registering icon.");
-                    tokio::time::sleep(Duration::from_millis(REREGISTER_DELAY_MS)).await;
-                    if let Err(e) = dbus::register_with_watcher(&conn_clone, &bus_name_clone).await {
-                        eprintln!("[Watcher] Failed to re-register icon: {}", e);
-                    }
-                }
-            }
-        }
-    });
+    // Re-register after Waybar restarts and reconnect if the bus connection drops.
+    // This also forwards thumbnail changes to the tray.
+    tokio::spawn(dbus::watchdog(BusAddress::Session, connection, bus_name.clone(), notifier_item, menu_model));
 
     // 7. Set up signal handlers
-    let app_class = app_config.class.clone();
+    let toggle_config = app_config.clone();
+    let toggle_state = Arc::clone(&state);
+    let toggle_backend = Arc::clone(&backend);
+    let toggle_thumbnails = thumbnails.clone();
+    let toggle_window = window_info.clone();
     let mut sigusr1 = signal(SignalKind::user_defined1())
         .context("Failed to create SIGUSR1 handler")?;
     
     tokio::spawn(async move {
         while sigusr1.recv().await.is_some() {
             println!("[Signal] Received SIGUSR1 - Toggling window");
-            if let Err(e) = hyprland::handle_window_toggle(&app_class).await {
-                eprintln!("[Signal] Failed to handle toggle: {}", e);
+            toggle_state.record_toggle();
+            match hyprland::handle_window_toggle(&*toggle_backend, &toggle_config, &toggle_window.address(), &toggle_state.held_by_others(), &toggle_thumbnails).await {
+                Ok(action) => toggle_state.record_action(action),
+                Err(e) => eprintln!("[Signal] Failed to handle toggle: {}", e),
             }
         }
     });
 
-    // 8. Start a background check to see if the window is closed
-    let window_address = window_info.address.clone();
-    let exit_notify_clone = Arc::clone(&exit_notify);
-    tokio::spawn(async move {
-        let mut check_interval = interval(Duration::from_secs(WINDOW_CHECK_INTERVAL_SECS));
-        loop {
-            check_interval.tick().await;
-            match hyprland::hyprctl::<Vec<WindowInfo>>("clients") {
-                Ok(clients) => {
-                    // Exit only if the window is completely closed
-                    if !clients.iter().any(|c| c.address == window_address) {
-                        println!("Window closed. Exiting.");
-                        exit_notify_clone.notify_one();
-                        break;
-                    }
+    // 8. Start auto-minimize and singleton rules, sharing one Hyprland event stream
+    let auto_minimize_enabled = auto_minimize::is_enabled(&app_config);
+    let singleton_enabled = singleton::is_enabled(&app_config);
+    if auto_minimize_enabled || singleton_enabled {
+        match events::subscribe().await {
+            Ok(events) => {
+                let [focus_events, open_events] = events::fan_out(events);
+                if auto_minimize_enabled {
+                    println!("[AutoMinimize] Watching focus changes");
+                    tokio::spawn(auto_minimize::run(
+                        Arc::clone(&backend),
+                        focus_events,
+                        app_config.clone(),
+                        window_info.clone(),
+                        Arc::clone(&state),
+                        thumbnails.clone(),
+                    ));
                 }
-                Err(e) => {
-                    eprintln!("Error checking window state: {}", e);
-                    exit_notify_clone.notify_one();
-                    break;
+                // Handle duplicate windows of the app according to its singleton policy
+                if singleton_enabled {
+                    println!("[Singleton] Watching for duplicate windows");
+                    tokio::spawn(singleton::run(
+                        Arc::clone(&backend),
+                        open_events,
+                        app_config.clone(),
+                        window_info.clone(),
+                        Arc::clone(&state),
+                        thumbnails.clone(),
+                    ));
                 }
             }
+            Err(e) => eprintln!("[Events] Failed to subscribe to Hyprland events: {}", e),
         }
+    }
+
+    // 9. Start a background check to see if the window is closed
+    let watched_window = window_info.clone();
+    let exit_notify_clone = Arc::clone(&exit_notify);
+    let check_state = Arc::clone(&state);
+    let check_backend = Arc::clone(&backend);
+    let check_thumbnails = thumbnails.clone();
+    tokio::spawn(async move {
+        let period = Duration::from_secs(WINDOW_CHECK_INTERVAL_SECS);
+        // Keep minimized time and the tray preview accurate even when moved outside the daemon
+        let result = hyprland::watch_window(&*check_backend, &watched_window, period, |window| {
+            let minimized = window.workspace.id < 0;
+            check_state.set_minimized(minimized);
+            if !minimized {
+                check_thumbnails.clear();
+            }
+        })
+        .await;
+        // Exit only if the window is completely closed
+        match result {
+            Ok(()) => println!("Window closed. Exiting."),
+            Err(e) => eprintln!("Error checking window state: {}", e),
+        }
+        exit_notify_clone.notify_one();
     });
 
-    // 9. Wait for exit signal
+    // 10. Wait for exit signal; stop signals received during setup are delivered here
     println!("[Daemon] Running. Send SIGUSR1 to toggle, or close the window to exit.");
     tokio::select! {
-        _ = tokio::signal::ctrl_c() => {
-            println!("\n[Daemon] Interrupted by Ctrl+C.");
+        name = stop_signal(&mut sigterm, &mut sigint) => {
+            println!("[Daemon] Received {}.", name);
+            cleanup.apply_exit_policy();
         }
         _ = exit_notify.notified() => {
             println!("[Daemon] Window closed, exiting.");
         }
     }
 
-    // 10. Release the lock before exiting
-    lock::release_lock(&app_name);
+    // 11. Release the lock before exiting
+    cleanup_guard.disarm();
+    cleanup.release();
     
     println!("[Daemon] Exiting.");
     Ok(())
 }
+
+/// Waits for SIGTERM or SIGINT and returns the name of the signal received.
+async fn stop_signal(sigterm: &mut Signal, sigint: &mut Signal) -> &'static str {
+    tokio::select! {
+        _ = sigterm.recv() => "SIGTERM",
+        _ = sigint.recv() => "SIGINT",
+    }
+}
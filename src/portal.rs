@@ -0,0 +1,104 @@
+//! XDG Background portal integration for sandboxed environments.
+//!
+//! Inside Flatpak, processes may be killed when they have no visible window
+//! unless the user granted background permission. This module requests that
+//! permission (and, if the app opts in, autostart at login) through
+//! `org.freedesktop.portal.Background` so `launch_in_background` apps keep
+//! their tray icon under sandbox policies.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use tokio_stream::StreamExt;
+use zbus::zvariant::{OwnedValue, Value};
+
+/// D-Bus service name of the desktop portal.
+const PORTAL_NAME: &str = "org.freedesktop.portal.Desktop";
+
+/// D-Bus object path of the desktop portal.
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// Returns true when running inside a Flatpak sandbox.
+pub fn is_sandboxed() -> bool {
+    Path::new("/.flatpak-info").exists() || std::env::var_os("FLATPAK_ID").is_some()
+}
+
+/// Outcome of a background permission request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackgroundGrant {
+    /// Whether the process may keep running without a visible window
+    pub background: bool,
+    /// Whether an autostart entry was created
+    pub autostart: bool,
+}
+
+/// Requests background (and optionally autostart) permission from the portal.
+///
+/// Waits for the user's answer. Returns an error if the portal is unavailable
+/// or the request was cancelled.
+pub async fn request_background(
+    conn: &zbus::Connection,
+    reason: &str,
+    autostart: bool,
+    commandline: &[String],
+) -> Result<BackgroundGrant> {
+    // Subscribe to the response before sending the request to avoid a race
+    let token = format!("hyprland_minimizer_{}", std::process::id());
+    let sender = conn
+        .unique_name()
+        .context("D-Bus connection has no unique name")?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let request_path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+
+    let request = zbus::Proxy::new(
+        conn,
+        PORTAL_NAME,
+        request_path.as_str(),
+        "org.freedesktop.portal.Request",
+    )
+    .await?;
+    let mut responses = request.receive_signal("Response").await?;
+
+    let background = zbus::Proxy::new(conn, PORTAL_NAME, PORTAL_PATH, "org.freedesktop.portal.Background")
+        .await?;
+    let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+    options.insert("handle_token", Value::from(token.as_str()));
+    options.insert("reason", Value::from(reason));
+    options.insert("autostart", Value::from(autostart));
+    options.insert("commandline", Value::from(commandline.to_vec()));
+    options.insert("dbus-activatable", Value::from(false));
+
+    background
+        .call_method("RequestBackground", &("", options))
+        .await
+        .context("Background portal request failed")?;
+
+    let message = responses
+        .next()
+        .await
+        .context("Background portal closed without answering")?;
+    let (response, results): (u32, HashMap<String, OwnedValue>) = message.body()?;
+    parse_response(response, &results)
+}
+
+/// Reads the grant from the arguments of a portal `Response` signal.
+///
+/// Non-zero response codes mean the user denied (1) or the request was
+/// otherwise ended (2). Missing result keys count as not granted.
+pub fn parse_response(response: u32, results: &HashMap<String, OwnedValue>) -> Result<BackgroundGrant> {
+    if response != 0 {
+        anyhow::bail!("Background permission request was denied or cancelled (code {})", response);
+    }
+
+    let flag = |key: &str| {
+        results
+            .get(key)
+            .and_then(|v| bool::try_from(v.clone()).ok())
+            .unwrap_or(false)
+    };
+    Ok(BackgroundGrant {
+        background: flag("background"),
+        autostart: flag("autostart"),
+    })
+}
@@ -0,0 +1,99 @@
+//! Hyprland window rule generation.
+//!
+//! This module suggests `windowrulev2` lines for the configured apps so the
+//! compositor configuration matches what the minimizer expects: apps started
+//! in the background can open directly in their special workspace, and
+//! scratchpad-style apps (auto-minimized on focus loss or inactivity) float.
+//!
+//! The special workspace rule applies to every window of the class, including
+//! ones opened by hand, so it is emitted commented out for the user to opt in.
+
+use crate::auto_minimize;
+use crate::config::AppConfig;
+use std::collections::BTreeMap;
+
+/// Default floating size for scratchpad-style apps.
+const SCRATCHPAD_SIZE: &str = "60% 70%";
+
+/// Returns the suggested window rules for one app, without a header.
+pub fn app_rules(app_config: &AppConfig) -> Vec<String> {
+    let mut classes = vec![app_config.class.as_str()];
+    classes.extend(app_config.companion_classes.iter().map(String::as_str));
+    let matcher = format!(
+        "class:^({})$",
+        classes.iter().map(|c| escape_regex(c)).collect::<Vec<_>>().join("|")
+    );
+
+    let mut rules = Vec::new();
+    if app_config.launch_in_background.unwrap_or(false) {
+        rules.push("# Uncomment to start hidden; also hides windows of the app you open yourself".to_string());
+        rules.push(format!(
+            "# windowrulev2 = workspace special:{} silent, {}",
+            app_config.special_workspace(), matcher
+        ));
+    }
+    if auto_minimize::is_enabled(app_config) {
+        let main = format!("class:^({})$", escape_regex(&app_config.class));
+        rules.push(format!("windowrulev2 = float, {}", main));
+        rules.push(format!("windowrulev2 = size {}, {}", SCRATCHPAD_SIZE, main));
+        rules.push(format!("windowrulev2 = center, {}", main));
+    }
+    rules
+}
+
+/// Returns the window classes matched by more than one of the given apps,
+/// with the names of those apps.
+pub fn shared_classes<'a>(apps: &[(&'a str, &'a AppConfig)]) -> Vec<(&'a str, Vec<&'a str>)> {
+    let mut users: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (app_name, app_config) in apps {
+        let classes = std::iter::once(&app_config.class).chain(&app_config.companion_classes);
+        for class in classes {
+            let names = users.entry(class.as_str()).or_default();
+            if !names.contains(app_name) {
+                names.push(app_name);
+            }
+        }
+    }
+    users.into_iter().filter(|(_, names)| names.len() > 1).collect()
+}
+
+/// Renders the suggested rules for the given apps as a hyprland.conf snippet.
+///
+/// Classes shared by several apps get conflicting rules; they are flagged in
+/// the snippet and on stderr.
+pub fn generate(apps: &[(&str, &AppConfig)]) -> String {
+    let mut out = String::from("# Window rules generated by hyprland-minimizer gen-rules\n");
+    for (class, names) in shared_classes(apps) {
+        let warning = format!(
+            "Warning: class '{}' is used by apps {}; their rules conflict, keep only one set",
+            class,
+            names.join(", ")
+        );
+        eprintln!("[Rules] {}", warning);
+        out.push_str(&format!("# {}\n", warning));
+    }
+    for (app_name, app_config) in apps {
+        out.push_str(&format!("\n# {} ({})\n", app_config.name, app_name));
+        let rules = app_rules(app_config);
+        if rules.is_empty() {
+            out.push_str("# No rules needed\n");
+        }
+        for rule in rules {
+            out.push_str(&rule);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Escapes regex metacharacters in a window class.
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,129 @@
+//! Singleton enforcement driven by Hyprland window events.
+//!
+//! Launching a managed app again from an app launcher often opens a second
+//! window of the same class. This module watches `openwindow` events and,
+//! depending on the app's `singleton_policy`, either closes the duplicate and
+//! brings up the managed window, or adopts the new window in its place.
+//!
+//! Only top-level windows count as duplicates: floating windows of the app
+//! (dialogs, splash screens, picture-in-picture) are left alone.
+
+use crate::config::{AppConfig, SingletonPolicy};
+use crate::events::Event;
+use crate::hyprland::{self, HyprlandBackend, ManagedWindow};
+use crate::state::DaemonState;
+use crate::thumbnail::ThumbnailSlot;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Returns true if the app enforces a single window.
+pub fn is_enabled(app_config: &AppConfig) -> bool {
+    app_config.singleton_policy != SingletonPolicy::Off
+}
+
+/// Consumes window events and handles duplicate windows of the app.
+///
+/// Companion and floating windows are never treated as duplicates. Runs
+/// until the event channel is closed.
+pub async fn run(
+    backend: Arc<dyn HyprlandBackend>,
+    mut events: mpsc::UnboundedReceiver<Event>,
+    app_config: AppConfig,
+    window: ManagedWindow,
+    state: Arc<DaemonState>,
+    thumbnails: ThumbnailSlot,
+) {
+    while let Some(event) = events.recv().await {
+        let Event::OpenWindow { address, class, title, .. } = event else {
+            continue;
+        };
+        if class != app_config.class
+            || address == window.address()
+            || app_config.is_companion(&class, &title)
+        {
+            continue;
+        }
+        match backend.clients() {
+            Ok(clients) => match clients.iter().find(|c| c.address == address) {
+                Some(opened) if !opened.floating => {}
+                Some(_) => {
+                    println!("[Singleton] Ignoring floating window {} ('{}')", address, title);
+                    continue;
+                }
+                None => continue,
+            },
+            Err(e) => {
+                eprintln!("[Singleton] Failed to look up new window: {}", e);
+                continue;
+            }
+        }
+
+        let result = match app_config.singleton_policy {
+            SingletonPolicy::Off => Ok(()),
+            SingletonPolicy::Focus => {
+                println!("[Singleton] Closing duplicate window {} - focusing managed window", address);
+                close_duplicate(&*backend, &app_config, &window, &address, &state, &thumbnails).await
+            }
+            SingletonPolicy::Adopt => {
+                println!("[Singleton] Adopting new window {} (was {})", address, window.address());
+                adopt(&*backend, &window, &address, &title, &state, &thumbnails)
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("[Singleton] Failed to handle duplicate window: {}", e);
+        }
+    }
+}
+
+/// Closes the duplicate window and brings up the managed one.
+///
+/// A minimized window is restored like a toggle, a visible one is focused.
+async fn close_duplicate(
+    backend: &dyn HyprlandBackend,
+    app_config: &AppConfig,
+    window: &ManagedWindow,
+    duplicate: &str,
+    state: &DaemonState,
+    thumbnails: &ThumbnailSlot,
+) -> Result<()> {
+    backend.dispatch(&format!("closewindow address:{}", duplicate))?;
+
+    let address = window.address();
+    let clients = backend.clients()?;
+    match clients.iter().find(|c| c.address == address) {
+        Some(managed) if managed.workspace.id < 0 => {
+            state.record_toggle();
+            let action = hyprland::handle_window_toggle(backend, app_config, &address, &state.held_by_others(), thumbnails).await?;
+            state.record_action(action);
+        }
+        Some(_) => backend.dispatch(&format!("focuswindow address:{}", address))?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Manages the new window from now on.
+///
+/// The previously managed window is left alone, but moved out of the special
+/// workspace first so it does not stay hidden without a tray icon. The tray
+/// is told about the new title through [`ManagedWindow::changed`].
+fn adopt(
+    backend: &dyn HyprlandBackend,
+    window: &ManagedWindow,
+    address: &str,
+    title: &str,
+    state: &DaemonState,
+    thumbnails: &ThumbnailSlot,
+) -> Result<()> {
+    let previous = window.address();
+    window.adopt(address, title);
+    state.set_window(address, title);
+    thumbnails.clear();
+
+    let clients = backend.clients()?;
+    if clients.iter().any(|c| c.address == previous && c.workspace.id < 0) {
+        backend.dispatch(&format!("movetoworkspacesilent +0,address:{}", previous))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,266 @@
+//! Daemon state file and usage statistics.
+//!
+//! Each running daemon keeps a small JSON state file next to its lock file
+//! with usage counters (toggles, restores, minimized time, launch duration).
+//! The `status` subcommand reads these files to report on running daemons;
+//! files left behind by a daemon that was killed are detected by their PID
+//! and removed.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::hyprland::ToggleAction;
+use crate::lock;
+
+/// Usage counters persisted in the state file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Stats {
+    /// PID of the daemon process
+    pub pid: u32,
+    /// Window class being managed
+    pub class: String,
+    /// Address of the managed window
+    #[serde(default)]
+    pub address: String,
+    /// Title of the managed window
+    pub title: String,
+    /// Daemon start time (seconds since the Unix epoch)
+    pub started_at: u64,
+    /// Number of toggle requests (keybinding, tray click or menu)
+    pub toggle_count: u64,
+    /// Number of times the window was brought back from the special workspace
+    pub restore_count: u64,
+    /// Total seconds spent minimized, excluding the current minimized period
+    pub minimized_secs: u64,
+    /// Start of the current minimized period, if the window is minimized
+    pub minimized_since: Option<u64>,
+    /// Time from launch until the window appeared, if launched by the daemon
+    pub launch_duration_ms: Option<u64>,
+}
+
+impl Stats {
+    /// Returns the length of the current minimized period in seconds.
+    pub fn hidden_for_secs(&self) -> Option<u64> {
+        self.minimized_since.map(|since| now_secs().saturating_sub(since))
+    }
+
+    /// Returns total minimized time including the current period.
+    pub fn total_minimized_secs(&self) -> u64 {
+        self.minimized_secs + self.hidden_for_secs().unwrap_or(0)
+    }
+}
+
+/// Shared daemon state, written to disk on every change.
+pub struct DaemonState {
+    app_name: String,
+    path: PathBuf,
+    stats: Mutex<Stats>,
+}
+
+impl DaemonState {
+    /// Creates the state for a newly started daemon and writes the state file.
+    pub fn new(app_name: &str, stats: Stats) -> Self {
+        Self::in_dir(&lock::runtime_dir(), app_name, stats)
+    }
+
+    /// Like [`DaemonState::new`], but keeps the state file in `dir`.
+    pub fn in_dir(dir: &Path, app_name: &str, stats: Stats) -> Self {
+        let state = Self {
+            app_name: app_name.to_string(),
+            path: state_file_path(dir, app_name),
+            stats: Mutex::new(stats),
+        };
+        state.write(&state.snapshot());
+        state
+    }
+
+    /// Records a toggle request.
+    pub fn record_toggle(&self) {
+        self.update(|stats| stats.toggle_count += 1);
+    }
+
+    /// Records the address and title of the managed window after switching windows.
+    pub fn set_window(&self, address: &str, title: &str) {
+        self.update(|stats| {
+            stats.address = address.to_string();
+            stats.title = title.to_string();
+        });
+    }
+
+    /// Records whether the window is currently minimized.
+    ///
+    /// Transitions from minimized to visible are counted as restores.
+    pub fn set_minimized(&self, minimized: bool) {
+        let mut stats = self.stats.lock().unwrap();
+        match (stats.minimized_since, minimized) {
+            (None, true) => stats.minimized_since = Some(now_secs()),
+            (Some(since), false) => {
+                stats.minimized_secs += now_secs().saturating_sub(since);
+                stats.minimized_since = None;
+                stats.restore_count += 1;
+            }
+            _ => return,
+        }
+        self.write(&stats);
+    }
+
+    /// Records the outcome of a toggle or minimize operation.
+    pub fn record_action(&self, action: ToggleAction) {
+        match action {
+            ToggleAction::Minimized => self.set_minimized(true),
+            ToggleAction::Restored => self.set_minimized(false),
+            ToggleAction::NotFound => {}
+        }
+    }
+
+    /// Returns a snapshot of the current counters.
+    pub fn snapshot(&self) -> Stats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Returns the window addresses managed by the other running daemons.
+    pub fn held_by_others(&self) -> Vec<String> {
+        match self.path.parent() {
+            Some(dir) => held_addresses_from(dir, &self.app_name),
+            None => Vec::new(),
+        }
+    }
+
+    /// Removes the state file when the daemon exits.
+    pub fn remove(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+
+    /// Applies a change to the counters and persists them.
+    fn update(&self, f: impl FnOnce(&mut Stats)) {
+        let mut stats = self.stats.lock().unwrap();
+        f(&mut stats);
+        self.write(&stats);
+    }
+
+    /// Writes the state file, logging any failure.
+    fn write(&self, stats: &Stats) {
+        let result = serde_json::to_vec_pretty(stats)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| fs::write(&self.path, json).map_err(anyhow::Error::from));
+        if let Err(e) = result {
+            eprintln!("[State] Failed to write state file {:?}: {}", self.path, e);
+        }
+    }
+}
+
+/// Returns the path to the state file for a given application.
+fn state_file_path(dir: &Path, app_name: &str) -> PathBuf {
+    dir.join(format!("hyprland-minimizer-{}.state.json", app_name))
+}
+
+/// Reads the state file of a running daemon.
+///
+/// Returns `Ok(None)` if no daemon is running for the app.
+pub fn read(app_name: &str) -> Result<Option<Stats>> {
+    read_from(&lock::runtime_dir(), app_name)
+}
+
+/// Like [`read`], but looks for the state file in `dir`.
+///
+/// A state file whose daemon is no longer running is removed.
+pub fn read_from(dir: &Path, app_name: &str) -> Result<Option<Stats>> {
+    let path = state_file_path(dir, app_name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read state file: {:?}", path))?;
+    let stats: Stats = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse state file: {:?}", path))?;
+    if !lock::is_running(stats.pid as i32) {
+        eprintln!("[State] Removing stale state file of PID {}", stats.pid);
+        let _ = fs::remove_file(&path);
+        return Ok(None);
+    }
+    Ok(Some(stats))
+}
+
+/// Returns the window addresses managed by running daemons other than `app_name`.
+///
+/// Lets a daemon leave alone windows of a shared class that another daemon
+/// already took over.
+pub fn held_addresses(app_name: &str) -> Vec<String> {
+    held_addresses_from(&lock::runtime_dir(), app_name)
+}
+
+/// Like [`held_addresses`], but looks for the state files in `dir`.
+pub fn held_addresses_from(dir: &Path, app_name: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name();
+            let other = file_name
+                .to_str()?
+                .strip_prefix("hyprland-minimizer-")?
+                .strip_suffix(".state.json")?
+                .to_string();
+            if other == app_name {
+                return None;
+            }
+            match read_from(dir, &other) {
+                Ok(stats) => stats.map(|stats| stats.address).filter(|address| !address.is_empty()),
+                Err(e) => {
+                    eprintln!("[State] {}", e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Prints a status report for the given apps.
+pub fn print_status(app_names: &[&str]) -> Result<()> {
+    for app_name in app_names {
+        match read(app_name)? {
+            None => println!("{}: not running", app_name),
+            Some(stats) => {
+                println!("{}: running (PID {})", app_name, stats.pid);
+                println!("  Window:          '{}' ({})", stats.title, stats.class);
+                match stats.hidden_for_secs() {
+                    Some(secs) => println!("  State:           minimized for {}", format_duration(secs)),
+                    None => println!("  State:           visible"),
+                }
+                println!("  Uptime:          {}", format_duration(now_secs().saturating_sub(stats.started_at)));
+                println!("  Toggles:         {}", stats.toggle_count);
+                println!("  Restores:        {}", stats.restore_count);
+                println!("  Total minimized: {}", format_duration(stats.total_minimized_secs()));
+                if let Some(ms) = stats.launch_duration_ms {
+                    println!("  Launch duration: {:.1}s", ms as f64 / 1000.0);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Formats a number of seconds as a compact human-readable duration.
+fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h > 0 {
+        format!("{}h {:02}m {:02}s", h, m, s)
+    } else if m > 0 {
+        format!("{}m {:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
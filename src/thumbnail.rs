@@ -0,0 +1,144 @@
+//! Window thumbnails for the tray tooltip.
+//!
+//! Right before a window is minimized, a small screenshot of it is captured
+//! with `grim` and converted to the ARGB32 pixmap format used by the
+//! StatusNotifierItem `ToolTip` property, so hovering the tray icon shows
+//! what is hidden.
+//!
+//! `grim` captures a screen region rather than the window itself, so the
+//! capture is skipped when another window or a special workspace may cover
+//! it; see [`crate::hyprland::capture_thumbnail`].
+
+use anyhow::{Context, Result};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+use crate::hyprland::WindowInfo;
+
+/// Maximum width or height of a thumbnail in pixels.
+pub const THUMBNAIL_MAX_SIZE: usize = 256;
+
+/// An SNI pixmap: (width, height, ARGB32 pixels in network byte order).
+pub type Pixmap = (i32, i32, Vec<u8>);
+
+/// Latest thumbnail of the managed window, shared with the tray icon.
+#[derive(Clone, Default)]
+pub struct ThumbnailSlot {
+    pixmap: Arc<Mutex<Option<Pixmap>>>,
+    changed: Arc<Notify>,
+}
+
+impl ThumbnailSlot {
+    /// Stores a new thumbnail and wakes up listeners.
+    pub fn set(&self, pixmap: Pixmap) {
+        *self.pixmap.lock().unwrap() = Some(pixmap);
+        self.changed.notify_one();
+    }
+
+    /// Removes the thumbnail (e.g. when the window is visible again).
+    pub fn clear(&self) {
+        if self.pixmap.lock().unwrap().take().is_some() {
+            self.changed.notify_one();
+        }
+    }
+
+    /// Returns the current thumbnail, if any.
+    pub fn get(&self) -> Option<Pixmap> {
+        self.pixmap.lock().unwrap().clone()
+    }
+
+    /// Waits until the thumbnail changes.
+    pub async fn changed(&self) {
+        self.changed.notified().await
+    }
+}
+
+/// Captures the window's on-screen area with `grim` and scales it down.
+pub fn capture(window: &WindowInfo) -> Result<Pixmap> {
+    let geometry = format!(
+        "{},{} {}x{}",
+        window.at[0], window.at[1], window.size[0], window.size[1]
+    );
+    let output = Command::new("grim")
+        .args(["-g", &geometry, "-t", "ppm", "-"])
+        .output()
+        .context("Failed to execute grim")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("grim failed: {}", stderr.trim());
+    }
+
+    let (width, height, rgb) = parse_ppm(&output.stdout)?;
+    Ok(to_argb_pixmap(width, height, rgb, THUMBNAIL_MAX_SIZE))
+}
+
+/// Parses a binary (P6) PPM image with 8-bit channels.
+///
+/// Returns the width, height and RGB pixel data.
+pub fn parse_ppm(data: &[u8]) -> Result<(usize, usize, &[u8])> {
+    let mut fields = Vec::with_capacity(4);
+    let mut pos = 0;
+
+    while fields.len() < 4 {
+        // Skip whitespace and comments between header fields
+        while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+            if data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+            }
+            pos += 1;
+        }
+        let start = pos;
+        while pos < data.len() && !data[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            anyhow::bail!("Truncated PPM header");
+        }
+        fields.push(std::str::from_utf8(&data[start..pos])?);
+    }
+    // Exactly one whitespace byte separates the header from the pixel data
+    pos += 1;
+
+    if fields[0] != "P6" {
+        anyhow::bail!("Unsupported image format '{}'", fields[0]);
+    }
+    let width: usize = fields[1].parse().context("Invalid PPM width")?;
+    let height: usize = fields[2].parse().context("Invalid PPM height")?;
+    if fields[3] != "255" {
+        anyhow::bail!("Unsupported PPM max value '{}'", fields[3]);
+    }
+
+    let end = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(3))
+        .and_then(|n| n.checked_add(pos))
+        .context("PPM dimensions are too large")?;
+    let pixels = data.get(pos..end).context("Truncated PPM pixel data")?;
+    Ok((width, height, pixels))
+}
+
+/// Converts RGB pixels to an ARGB32 pixmap no larger than `max_size` on either side.
+pub fn to_argb_pixmap(width: usize, height: usize, rgb: &[u8], max_size: usize) -> Pixmap {
+    if width == 0 || height == 0 {
+        return (0, 0, Vec::new());
+    }
+    let scale = (width.max(height) as f64 / max_size as f64).max(1.0);
+    let out_width = ((width as f64 / scale) as usize).max(1);
+    let out_height = ((height as f64 / scale) as usize).max(1);
+
+    let mut argb = Vec::with_capacity(out_width * out_height * 4);
+    for y in 0..out_height {
+        let src_y = ((y as f64 * scale) as usize).min(height - 1);
+        for x in 0..out_width {
+            let src_x = ((x as f64 * scale) as usize).min(width - 1);
+            let i = (src_y * width + src_x) * 3;
+            argb.extend_from_slice(&[0xff, rgb[i], rgb[i + 1], rgb[i + 2]]);
+        }
+    }
+
+    (out_width as i32, out_height as i32, argb)
+}
@@ -0,0 +1,81 @@
+//! Autostart entry generation.
+
+mod common;
+
+use common::temp_dir;
+use hyprland_minimizer::autostart;
+use hyprland_minimizer::config::Config;
+use std::fs;
+
+fn config() -> Config {
+    toml::from_str(
+        r#"
+        [apps.spotify]
+        name = "Spotify"
+        class = "spotify"
+        command = ["spotify"]
+        launch_in_background = true
+
+        [apps.discord]
+        name = "Discord"
+        class = "discord"
+        command = ["discord"]
+        launch_in_background = true
+
+        [apps.kitty]
+        name = "Kitty"
+        class = "kitty"
+        command = ["kitty"]
+        "#,
+    )
+    .unwrap()
+}
+
+fn names(config: &Config, apps: &[&str]) -> Vec<String> {
+    let apps: Vec<String> = apps.iter().map(|a| a.to_string()).collect();
+    autostart::select_apps(config, &apps)
+        .unwrap()
+        .into_iter()
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+#[test]
+fn selects_background_apps_by_default() {
+    assert_eq!(names(&config(), &[]), ["discord", "spotify"]);
+}
+
+#[test]
+fn skips_explicit_apps_without_background_launch() {
+    let config = config();
+
+    assert_eq!(names(&config, &["kitty", "spotify"]), ["spotify"]);
+    assert!(autostart::select_apps(&config, &["steam".to_string()]).is_err());
+}
+
+#[test]
+fn quotes_exec_arguments_per_desktop_entry_spec() {
+    assert_eq!(autostart::desktop_exec(&["/usr/bin/hyprland-minimizer", "spotify"]), "/usr/bin/hyprland-minimizer spotify");
+    assert_eq!(
+        autostart::desktop_exec(&["/home/me/My Apps/hyprland-minimizer", "spotify"]),
+        "\"/home/me/My Apps/hyprland-minimizer\" spotify"
+    );
+    assert_eq!(autostart::desktop_exec(&["/opt/$dir/100%"]), "\"/opt/\\\\$dir/100%%\"");
+}
+
+#[test]
+fn exec_once_lines_are_added_only_once() {
+    let config = config();
+    let selected = autostart::select_apps(&config, &[]).unwrap();
+    let path = temp_dir("exec-once").join("hyprland.conf");
+    fs::write(&path, "monitor = ,preferred,auto,1").unwrap();
+
+    autostart::append_exec_once(&path, "/home/me/My Apps/hyprland-minimizer", &selected).unwrap();
+    let first = fs::read_to_string(&path).unwrap();
+    autostart::append_exec_once(&path, "/home/me/My Apps/hyprland-minimizer", &selected).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), first);
+    assert!(first.starts_with("monitor = ,preferred,auto,1\n"));
+    assert_eq!(first.matches("exec-once = '/home/me/My Apps/hyprland-minimizer' spotify\n").count(), 1);
+    assert_eq!(first.matches("exec-once").count(), 2);
+}
@@ -0,0 +1,118 @@
+//! Crash cleanup against the fake compositor.
+
+mod common;
+
+use common::{app_config, daemon_state, managed, temp_dir, window, FakeBackend, SPECIAL_WORKSPACE_ID};
+use hyprland_minimizer::cleanup::Cleanup;
+use hyprland_minimizer::hyprland::ManagedWindow;
+use hyprland_minimizer::state;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Returns the cleanup of a "spotify" daemon and the directory holding its state file.
+fn cleanup(test: &str, fake: &Arc<FakeBackend>, window: ManagedWindow, extra: &str) -> (Cleanup, PathBuf) {
+    let dir = temp_dir(test);
+    let app_name = format!("test-{}", test);
+    let state = daemon_state(&dir, &app_name);
+    let cleanup = Cleanup::new(&app_name, app_config(extra), window, state, fake.clone());
+    (cleanup, dir)
+}
+
+#[test]
+fn guard_restores_hidden_window_to_original_workspace() {
+    let fake = Arc::new(FakeBackend::new(
+        4,
+        vec![
+            window("0x1", "spotify", SPECIAL_WORKSPACE_ID),
+            window("0x2", "spotify-helper", SPECIAL_WORKSPACE_ID),
+        ],
+    ));
+    let (cleanup, dir) = cleanup("cleanup-guard", &fake, managed("0x1"), "companion_classes = [\"spotify-helper\"]");
+    assert!(state::read_from(&dir, "test-cleanup-guard").unwrap().is_some());
+
+    drop(cleanup.guard());
+
+    // managed() remembers workspace 1 as the original one
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 1);
+    assert_eq!(fake.window("0x2").unwrap().workspace.id, 1);
+    assert!(state::read_from(&dir, "test-cleanup-guard").unwrap().is_none());
+}
+
+#[test]
+fn disarmed_guard_leaves_window_alone() {
+    let fake = Arc::new(FakeBackend::new(4, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]));
+    let (cleanup, _) = cleanup("cleanup-disarm", &fake, managed("0x1"), "");
+
+    cleanup.guard().disarm();
+    cleanup.release();
+
+    assert!(fake.special_of("0x1").is_some());
+    assert!(fake.dispatched().is_empty());
+}
+
+#[test]
+fn window_minimized_at_startup_goes_to_active_workspace() {
+    let fake = Arc::new(FakeBackend::new(4, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]));
+    let window = ManagedWindow::new(window("0x1", "spotify", SPECIAL_WORKSPACE_ID));
+    let (cleanup, _) = cleanup("cleanup-startup", &fake, window, "");
+
+    assert!(cleanup.restore_window().unwrap());
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 4);
+    // Visible windows are not touched
+    assert!(!cleanup.restore_window().unwrap());
+    cleanup.release();
+}
+
+#[test]
+fn exit_policy_restores_window_by_default() {
+    let fake = Arc::new(FakeBackend::new(4, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]));
+    let (cleanup, _) = cleanup("cleanup-exit-default", &fake, managed("0x1"), "");
+
+    cleanup.apply_exit_policy();
+    cleanup.release();
+
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 1);
+}
+
+#[test]
+fn exit_policy_can_leave_window_minimized() {
+    let fake = Arc::new(FakeBackend::new(4, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]));
+    let (cleanup, _) = cleanup("cleanup-exit-leave", &fake, managed("0x1"), "on_exit = \"leave-minimized\"");
+
+    cleanup.apply_exit_policy();
+    cleanup.release();
+
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("spotify"));
+    assert!(fake.dispatched().is_empty());
+}
+
+#[test]
+fn exit_policy_can_close_window_group() {
+    let fake = Arc::new(FakeBackend::new(
+        1,
+        vec![window("0x1", "spotify", 1), window("0x2", "spotify-helper", 1)],
+    ));
+    let extra = "on_exit = \"close-window\"\ncompanion_classes = [\"spotify-helper\"]";
+    let (cleanup, _) = cleanup("cleanup-exit-close", &fake, managed("0x1"), extra);
+
+    cleanup.apply_exit_policy();
+    cleanup.release();
+
+    assert!(fake.window("0x1").is_none());
+    assert!(fake.window("0x2").is_none());
+}
+
+#[test]
+fn close_policy_leaves_other_apps_with_matching_titles_open() {
+    let mut browser = window("0x2", "firefox", 1);
+    browser.title = "Mini Player - Mozilla Firefox".to_string();
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1), browser]));
+    let extra = "on_exit = \"close-window\"\ncompanion_titles = [\"Mini Player\"]";
+    let (cleanup, _) = cleanup("cleanup-exit-close-titles", &fake, managed("0x1"), extra);
+
+    cleanup.apply_exit_policy();
+    cleanup.release();
+
+    assert!(fake.window("0x1").is_none());
+    assert!(fake.window("0x2").is_some());
+}
@@ -0,0 +1,297 @@
+//! In-memory Hyprland backend for integration tests.
+//!
+//! `FakeBackend` keeps a scripted list of clients and interprets the subset of
+//! dispatchers used by the daemon, so tests can assert on the resulting window
+//! layout as well as on the exact dispatcher sequence.
+
+#![allow(dead_code)]
+
+use anyhow::Result;
+use hyprland_minimizer::config::AppConfig;
+use hyprland_minimizer::hyprland::{CursorPos, HyprlandBackend, ManagedWindow, Monitor, WindowInfo, Workspace};
+use hyprland_minimizer::state::{DaemonState, Stats};
+use hyprland_minimizer::thumbnail::Pixmap;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Workspace id used for all special workspaces in the fake compositor.
+pub const SPECIAL_WORKSPACE_ID: i32 = -99;
+
+/// A change applied to the fake compositor after a number of client queries.
+pub enum Scripted {
+    /// A new window appears
+    Open(WindowInfo),
+    /// A window is closed
+    Close(String),
+    /// Client queries start failing
+    Fail,
+    /// Client queries succeed again
+    Recover,
+}
+
+#[derive(Default)]
+struct FakeState {
+    clients: Vec<WindowInfo>,
+    active_workspace: i32,
+    focused: Option<String>,
+    shown_special: Option<String>,
+    dispatched: Vec<String>,
+    client_queries: usize,
+    script: VecDeque<(usize, Scripted)>,
+    failing: bool,
+    cursor: Option<CursorPos>,
+    monitors: Vec<Monitor>,
+}
+
+/// Fake compositor implementing [`HyprlandBackend`].
+pub struct FakeBackend {
+    state: Mutex<FakeState>,
+}
+
+/// Builds a window on the given workspace.
+pub fn window(address: &str, class: &str, workspace: i32) -> WindowInfo {
+    WindowInfo {
+        address: address.to_string(),
+        workspace: workspace_named(workspace, class),
+        title: format!("{} window", class),
+        class: class.to_string(),
+        at: [100, 100],
+        size: [800, 600],
+        floating: false,
+    }
+}
+
+/// Builds the daemon's handle for a "spotify" window on workspace 1.
+pub fn managed(address: &str) -> ManagedWindow {
+    ManagedWindow::new(window(address, "spotify", 1))
+}
+
+/// Builds a workspace; special ones are named after `special`.
+pub fn workspace_named(id: i32, special: &str) -> Workspace {
+    let name = if id < 0 {
+        format!("special:{}", special)
+    } else {
+        id.to_string()
+    };
+    Workspace { id, name }
+}
+
+/// Builds a config for the "spotify" app with extra TOML keys.
+pub fn app_config(extra: &str) -> AppConfig {
+    toml::from_str(&format!(
+        "name = \"Spotify\"\nclass = \"spotify\"\ncommand = [\"spotify\"]\n{}",
+        extra
+    ))
+    .unwrap()
+}
+
+/// Creates an empty scratch directory unique to the test and process.
+pub fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hyprland-minimizer-test-{}-{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Creates the state of a daemon in this process, with its state file in `dir`.
+pub fn daemon_state(dir: &Path, app_name: &str) -> Arc<DaemonState> {
+    let stats = Stats {
+        pid: std::process::id(),
+        ..Stats::default()
+    };
+    Arc::new(DaemonState::in_dir(dir, app_name, stats))
+}
+
+/// Builds a 1920x1080 monitor at the given layout offset showing `workspace`.
+pub fn monitor(x: i32, workspace: i32) -> Monitor {
+    Monitor {
+        x,
+        y: 0,
+        width: 1920,
+        height: 1080,
+        scale: 1.0,
+        transform: 0,
+        active_workspace: workspace_named(workspace, ""),
+        special_workspace: Workspace::default(),
+    }
+}
+
+impl FakeBackend {
+    /// Creates a compositor with the given windows, focused on `active_workspace`.
+    pub fn new(active_workspace: i32, clients: Vec<WindowInfo>) -> Self {
+        Self {
+            state: Mutex::new(FakeState {
+                clients,
+                active_workspace,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Schedules a change to happen once `after_queries` client queries were made.
+    pub fn schedule(&self, after_queries: usize, change: Scripted) {
+        self.state.lock().unwrap().script.push_back((after_queries, change));
+    }
+
+    /// Sets up the monitor layout and cursor position.
+    pub fn set_layout(&self, monitors: Vec<Monitor>, cursor: (i32, i32)) {
+        let mut state = self.state.lock().unwrap();
+        state.monitors = monitors;
+        state.cursor = Some(CursorPos { x: cursor.0, y: cursor.1 });
+    }
+
+    /// Returns all dispatcher commands received so far.
+    pub fn dispatched(&self) -> Vec<String> {
+        self.state.lock().unwrap().dispatched.clone()
+    }
+
+    /// Returns the current state of a window.
+    pub fn window(&self, address: &str) -> Option<WindowInfo> {
+        let state = self.state.lock().unwrap();
+        state.clients.iter().find(|c| c.address == address).cloned()
+    }
+
+    /// Returns the special workspace a window was moved to, if any.
+    pub fn special_of(&self, address: &str) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        let window = state.clients.iter().find(|c| c.address == address)?;
+        window.workspace.name.strip_prefix("special:").map(str::to_string)
+    }
+
+    /// Gives keyboard focus to a window.
+    pub fn focus(&self, address: &str) {
+        self.state.lock().unwrap().focused = Some(address.to_string());
+    }
+
+    /// Returns the address of the focused window.
+    pub fn focused(&self) -> Option<String> {
+        self.state.lock().unwrap().focused.clone()
+    }
+}
+
+impl FakeState {
+    fn run_script(&mut self) {
+        while let Some((after, _)) = self.script.front() {
+            if *after > self.client_queries {
+                break;
+            }
+            let (_, change) = self.script.pop_front().unwrap();
+            match change {
+                Scripted::Open(window) => self.clients.push(window),
+                Scripted::Close(address) => self.clients.retain(|c| c.address != address),
+                Scripted::Fail => self.failing = true,
+                Scripted::Recover => self.failing = false,
+            }
+        }
+    }
+
+    fn target_address(&self, selector: Option<&str>) -> Option<String> {
+        match selector {
+            Some(sel) => sel.strip_prefix("address:").map(str::to_string),
+            None => self.focused.clone(),
+        }
+    }
+
+    fn move_window(&mut self, address: &str, target: &str, silent: bool) {
+        let active = self.active_workspace;
+        let Some(window) = self.clients.iter_mut().find(|c| c.address == address) else {
+            return;
+        };
+        if let Some(name) = target.strip_prefix("special:") {
+            window.workspace = workspace_named(SPECIAL_WORKSPACE_ID, name);
+        } else if target == "+0" {
+            window.workspace = workspace_named(active, "");
+        } else if let Ok(id) = target.parse() {
+            window.workspace = workspace_named(id, "");
+        }
+        if !silent && window.workspace.id >= 0 {
+            self.active_workspace = window.workspace.id;
+            self.focused = Some(address.to_string());
+        }
+    }
+
+    fn apply(&mut self, command: &str) {
+        let (name, args) = command.split_once(' ').unwrap_or((command, ""));
+        match name {
+            "movetoworkspace" | "movetoworkspacesilent" => {
+                let (target, selector) = match args.split_once(',') {
+                    Some((target, selector)) => (target, Some(selector)),
+                    None => (args, None),
+                };
+                if let Some(address) = self.target_address(selector) {
+                    self.move_window(&address, target, name == "movetoworkspacesilent");
+                }
+            }
+            "togglespecialworkspace" => {
+                if self.shown_special.as_deref() == Some(args) {
+                    self.shown_special = None;
+                } else {
+                    self.shown_special = Some(args.to_string());
+                    self.focused = self
+                        .clients
+                        .iter()
+                        .find(|c| c.workspace.name.strip_prefix("special:") == Some(args))
+                        .map(|c| c.address.clone());
+                }
+            }
+            "focuswindow" => {
+                if let Some(address) = args.strip_prefix("address:") {
+                    self.focused = Some(address.to_string());
+                } else if let Some(class) = args.strip_prefix("initialclass:") {
+                    self.focused = self
+                        .clients
+                        .iter()
+                        .find(|c| c.class == class)
+                        .map(|c| c.address.clone());
+                }
+            }
+            "closewindow" => {
+                if let Some(address) = args.strip_prefix("address:") {
+                    self.clients.retain(|c| c.address != address);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl HyprlandBackend for FakeBackend {
+    fn clients(&self) -> Result<Vec<WindowInfo>> {
+        let mut state = self.state.lock().unwrap();
+        state.client_queries += 1;
+        state.run_script();
+        if state.failing {
+            anyhow::bail!("fake compositor is gone");
+        }
+        Ok(state.clients.clone())
+    }
+
+    fn active_workspace(&self) -> Result<Workspace> {
+        Ok(workspace_named(self.state.lock().unwrap().active_workspace, ""))
+    }
+
+    fn active_window_address(&self) -> Result<Option<String>> {
+        Ok(self.state.lock().unwrap().focused.clone())
+    }
+
+    fn dispatch(&self, command: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.dispatched.push(command.to_string());
+        state.apply(command);
+        Ok(())
+    }
+
+    fn cursor_pos(&self) -> Result<CursorPos> {
+        Ok(self.state.lock().unwrap().cursor.unwrap_or(CursorPos { x: 0, y: 0 }))
+    }
+
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        Ok(self.state.lock().unwrap().monitors.clone())
+    }
+
+    fn capture(&self, window: &WindowInfo) -> Result<Pixmap> {
+        // A 1x1 pixmap tagged with the window's workspace id
+        Ok((1, 1, vec![0xff, 0, 0, window.workspace.id as u8]))
+    }
+}
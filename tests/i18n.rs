@@ -0,0 +1,45 @@
+//! Menu label localization.
+
+use hyprland_minimizer::i18n::{self, LabelOverrides, Labels};
+use std::collections::HashMap;
+
+fn language(vars: &[(&str, &str)]) -> String {
+    let vars: HashMap<_, _> = vars.iter().copied().collect();
+    i18n::detect_language(|name| vars.get(name).map(|v| v.to_string()))
+}
+
+#[test]
+fn renders_placeholders_from_title_literally() {
+    assert_eq!(
+        i18n::render("Restore {title} ({workspace})", "Notes {workspace}", 3),
+        "Restore Notes {workspace} (3)"
+    );
+    assert_eq!(i18n::render("{title} {x}", "a{title", 0), "a{title {x}");
+}
+
+#[test]
+fn detects_language_from_locale() {
+    assert_eq!(language(&[("LANG", "it_IT.UTF-8")]), "it");
+    assert_eq!(language(&[("LC_ALL", "de_DE.UTF-8"), ("LANG", "it_IT.UTF-8")]), "de");
+    assert_eq!(language(&[]), "en");
+}
+
+#[test]
+fn language_list_takes_priority() {
+    let vars = [("LANGUAGE", "eo:fr_FR:de"), ("LC_ALL", "it_IT.UTF-8")];
+    assert_eq!(language(&vars), "fr");
+    // LANGUAGE is ignored for the C locale, like gettext does
+    assert_eq!(language(&[("LANGUAGE", "fr"), ("LANG", "C")]), "c");
+}
+
+#[test]
+fn overrides_replace_bundled_labels() {
+    let overrides = LabelOverrides {
+        close: Some("Quit {title}".to_string()),
+        ..LabelOverrides::default()
+    };
+    let labels = Labels::for_language("it", &overrides);
+
+    assert_eq!(labels.toggle("Spotify"), "Mostra/nascondi Spotify");
+    assert_eq!(labels.close("Spotify"), "Quit Spotify");
+}
@@ -0,0 +1,36 @@
+//! Custom icon directory lookup.
+
+mod common;
+
+use common::temp_dir;
+use hyprland_minimizer::icons;
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn prefers_directory_containing_the_icon() {
+    let empty = temp_dir("icons-empty");
+    let themed = temp_dir("icons-themed");
+    fs::create_dir_all(themed.join("hicolor/48x48/apps")).unwrap();
+    fs::write(themed.join("hicolor/48x48/apps/my-app.png"), b"").unwrap();
+    let dirs = [empty.to_string_lossy().into_owned(), themed.to_string_lossy().into_owned()];
+
+    assert_eq!(icons::icon_theme_path(&dirs, "my-app"), Some(themed.clone()));
+    assert_eq!(icons::icon_theme_path(&dirs, "other"), Some(empty.clone()));
+
+    fs::remove_dir_all(empty).unwrap();
+    fs::remove_dir_all(themed).unwrap();
+}
+
+#[test]
+fn ignores_missing_directories() {
+    assert_eq!(icons::icon_theme_path(&["/nonexistent/icons".to_string()], "my-app"), None);
+}
+
+#[test]
+fn expands_home_directory() {
+    let home = icons::expand_home("~");
+    assert!(!home.starts_with("~"));
+    assert_eq!(icons::expand_home("~/icons"), home.join("icons"));
+    assert_eq!(icons::expand_home("/abs/~icons"), PathBuf::from("/abs/~icons"));
+}
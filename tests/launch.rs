@@ -0,0 +1,39 @@
+//! Launch waiting against the fake compositor.
+
+mod common;
+
+use common::{app_config, window, FakeBackend, Scripted};
+use hyprland_minimizer::launcher;
+
+#[tokio::test(start_paused = true)]
+async fn finds_window_once_it_appears() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "firefox", 1)]);
+    fake.schedule(3, Scripted::Open(window("0x2", "spotify", 1)));
+
+    let found = launcher::wait_for_window(&fake, &app_config(""), &[], 10).await;
+
+    assert_eq!(found.unwrap().address, "0x2");
+}
+
+#[tokio::test(start_paused = true)]
+async fn gives_up_after_timeout() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "firefox", 1)]);
+    let started = tokio::time::Instant::now();
+
+    let found = launcher::wait_for_window(&fake, &app_config(""), &[], 8).await;
+
+    assert!(found.is_none());
+    assert_eq!(started.elapsed().as_secs(), 8);
+}
+
+#[tokio::test(start_paused = true)]
+async fn tolerates_transient_errors_while_waiting() {
+    let fake = FakeBackend::new(1, vec![]);
+    fake.schedule(1, Scripted::Fail);
+    fake.schedule(4, Scripted::Recover);
+    fake.schedule(5, Scripted::Open(window("0x1", "spotify", 1)));
+
+    let found = launcher::wait_for_window(&fake, &app_config(""), &[], 5).await;
+
+    assert_eq!(found.map(|w| w.address).as_deref(), Some("0x1"));
+}
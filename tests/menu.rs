@@ -0,0 +1,118 @@
+//! Tray menu actions against the fake compositor.
+
+mod common;
+
+use common::{app_config, daemon_state, managed, temp_dir, window, FakeBackend, SPECIAL_WORKSPACE_ID};
+use hyprland_minimizer::config::AppConfig;
+use hyprland_minimizer::dbus::{GtkActions, GtkMenus, MenuAction, MenuModel};
+use hyprland_minimizer::i18n::{LabelOverrides, Labels};
+use hyprland_minimizer::thumbnail::ThumbnailSlot;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::net::UnixStream;
+use tokio::sync::Notify;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::{Connection, ConnectionBuilder, Guid};
+
+/// A menu group as returned by `org.gtk.Menus.Start`.
+type MenuGroup = (u32, u32, Vec<HashMap<String, OwnedValue>>);
+
+fn menu(test: &str, fake: &Arc<FakeBackend>, app_config: AppConfig) -> MenuModel {
+    MenuModel {
+        backend: fake.clone(),
+        app_config,
+        window_info: managed("0x1"),
+        exit_notify: Arc::new(Notify::new()),
+        state: daemon_state(&temp_dir(test), "spotify"),
+        labels: Labels::for_language("en", &LabelOverrides::default()),
+        thumbnails: ThumbnailSlot::default(),
+    }
+}
+
+#[test]
+fn restore_moves_window_group_to_original_workspace() {
+    let fake = Arc::new(FakeBackend::new(
+        4,
+        vec![
+            window("0x1", "spotify", SPECIAL_WORKSPACE_ID),
+            window("0x2", "spotify-helper", SPECIAL_WORKSPACE_ID),
+        ],
+    ));
+    let menu = menu("menu-restore", &fake, app_config("companion_classes = [\"spotify-helper\"]"));
+    menu.state.set_minimized(true);
+
+    menu.activate(MenuAction::Restore);
+
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 1);
+    assert_eq!(fake.window("0x2").unwrap().workspace.id, 1);
+    assert_eq!(fake.focused().as_deref(), Some("0x1"));
+    assert_eq!(menu.state.snapshot().restore_count, 1);
+}
+
+#[tokio::test]
+async fn close_closes_window_and_stops_daemon() {
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1), window("0x2", "kitty", 1)]));
+    let menu = menu("menu-close", &fake, app_config(""));
+
+    menu.activate(MenuAction::Close);
+
+    assert!(fake.window("0x1").is_none());
+    assert!(fake.window("0x2").is_some());
+    // The stop request is stored until the daemon waits for it
+    menu.exit_notify.notified().await;
+}
+
+/// Serves the GMenu export of `menu` on a peer-to-peer connection and returns the client side.
+async fn gmenu_client(menu: MenuModel) -> (Connection, Connection) {
+    let menu = Arc::new(menu);
+    let (server, client) = UnixStream::pair().unwrap();
+    let guid = Guid::generate();
+    let server = ConnectionBuilder::unix_stream(server)
+        .server(&guid)
+        .p2p()
+        .serve_at("/Menu", GtkMenus { model: Arc::clone(&menu) })
+        .unwrap()
+        .serve_at("/Menu", GtkActions { model: menu })
+        .unwrap()
+        .build();
+    let client = ConnectionBuilder::unix_stream(client).p2p().build();
+    let (server, client) = tokio::join!(server, client);
+    (server.unwrap(), client.unwrap())
+}
+
+#[tokio::test]
+async fn gmenu_export_lists_items_with_actions() {
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1)]));
+    let (_server, client) = gmenu_client(menu("gmenu-start", &fake, app_config(""))).await;
+
+    let reply = client
+        .call_method(None::<()>, "/Menu", Some("org.gtk.Menus"), "Start", &(vec![0u32],))
+        .await
+        .unwrap();
+    let groups: Vec<MenuGroup> = reply.body().unwrap();
+
+    let (_, _, items) = &groups[0];
+    let actions: Vec<String> = items
+        .iter()
+        .map(|item| String::try_from(Value::from(item["action"].clone())).unwrap())
+        .collect();
+    assert_eq!(actions, ["app.toggle", "app.restore", "app.close"]);
+    let label = String::try_from(Value::from(items[2]["label"].clone())).unwrap();
+    assert_eq!(label, "Close spotify window");
+}
+
+#[tokio::test]
+async fn gmenu_activate_restores_window() {
+    let fake = Arc::new(FakeBackend::new(4, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]));
+    let (_server, client) = gmenu_client(menu("gmenu-activate", &fake, app_config(""))).await;
+
+    let parameter: Vec<OwnedValue> = Vec::new();
+    let platform_data: HashMap<String, OwnedValue> = HashMap::new();
+    client
+        .call_method(None::<()>, "/Menu", Some("org.gtk.Actions"), "Activate", &("restore", parameter, platform_data))
+        .await
+        .unwrap();
+
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 1);
+    assert_eq!(fake.focused().as_deref(), Some("0x1"));
+}
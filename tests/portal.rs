@@ -0,0 +1,40 @@
+//! Background portal response handling.
+
+use hyprland_minimizer::portal::{self, BackgroundGrant};
+use std::collections::HashMap;
+use zbus::zvariant::{OwnedValue, Value};
+
+fn results(entries: &[(&str, Value<'static>)]) -> HashMap<String, OwnedValue> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), OwnedValue::from(value.clone())))
+        .collect()
+}
+
+#[test]
+fn reads_granted_permissions() {
+    let results = results(&[("background", Value::from(true)), ("autostart", Value::from(false))]);
+
+    assert_eq!(
+        portal::parse_response(0, &results).unwrap(),
+        BackgroundGrant { background: true, autostart: false }
+    );
+}
+
+#[test]
+fn missing_or_mistyped_results_are_not_granted() {
+    let results = results(&[("background", Value::from("yes"))]);
+
+    assert_eq!(
+        portal::parse_response(0, &results).unwrap(),
+        BackgroundGrant { background: false, autostart: false }
+    );
+}
+
+#[test]
+fn denied_and_cancelled_requests_fail() {
+    let results = results(&[("background", Value::from(true))]);
+
+    assert!(portal::parse_response(1, &results).is_err());
+    assert!(portal::parse_response(2, &results).is_err());
+}
@@ -0,0 +1,52 @@
+//! Window rule generation.
+
+mod common;
+
+use common::app_config;
+use hyprland_minimizer::rules;
+
+#[test]
+fn background_apps_can_opt_in_to_their_special_workspace() {
+    let config = app_config("launch_in_background = true\ncompanion_classes = [\"spotify.helper\"]");
+
+    let rules = rules::app_rules(&config);
+    assert_eq!(
+        rules.last().unwrap(),
+        "# windowrulev2 = workspace special:spotify silent, class:^(spotify|spotify\\.helper)$"
+    );
+    assert!(rules.iter().all(|rule| rule.starts_with('#')));
+}
+
+#[test]
+fn scratchpad_apps_float_centered() {
+    let config = app_config("auto_minimize_on_unfocus = true");
+
+    assert_eq!(
+        rules::app_rules(&config),
+        [
+            "windowrulev2 = float, class:^(spotify)$",
+            "windowrulev2 = size 60% 70%, class:^(spotify)$",
+            "windowrulev2 = center, class:^(spotify)$",
+        ]
+    );
+}
+
+#[test]
+fn plain_apps_need_no_rules() {
+    let config = app_config("");
+
+    assert!(rules::app_rules(&config).is_empty());
+    assert!(rules::generate(&[("spotify", &config)]).contains("# No rules needed"));
+}
+
+#[test]
+fn warns_about_apps_sharing_a_class() {
+    let player = app_config("auto_minimize_on_unfocus = true");
+    let mini = app_config("companion_classes = [\"spotify-helper\"]");
+    let mut other = app_config("");
+    other.class = "kitty".to_string();
+    let apps = [("player", &player), ("mini", &mini), ("term", &other)];
+
+    assert_eq!(rules::shared_classes(&apps), [("spotify", vec!["player", "mini"])]);
+    assert!(rules::generate(&apps).contains("# Warning: class 'spotify' is used by apps player, mini"));
+}
@@ -0,0 +1,124 @@
+//! Duplicate window handling against the fake compositor.
+
+mod common;
+
+use common::{app_config, daemon_state, managed, temp_dir, window, FakeBackend, SPECIAL_WORKSPACE_ID};
+use hyprland_minimizer::config::AppConfig;
+use hyprland_minimizer::events::{self, Event};
+use hyprland_minimizer::hyprland::ManagedWindow;
+use hyprland_minimizer::singleton;
+use hyprland_minimizer::state::DaemonState;
+use hyprland_minimizer::thumbnail::ThumbnailSlot;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+fn opened(address: &str, class: &str, title: &str) -> Event {
+    Event::OpenWindow {
+        address: address.to_string(),
+        workspace: "1".to_string(),
+        class: class.to_string(),
+        title: title.to_string(),
+    }
+}
+
+/// Runs the singleton loop over the given events until they are consumed.
+async fn run(
+    test: &str,
+    fake: &Arc<FakeBackend>,
+    config: AppConfig,
+    window: &ManagedWindow,
+    events: Vec<Event>,
+) -> Arc<DaemonState> {
+    let state = daemon_state(&temp_dir(test), "spotify");
+    let (tx, rx) = mpsc::unbounded_channel();
+    for event in events {
+        tx.send(event).unwrap();
+    }
+    drop(tx);
+    singleton::run(fake.clone(), rx, config, window.clone(), state.clone(), ThumbnailSlot::default()).await;
+    state
+}
+
+#[test]
+fn parses_openwindow_event() {
+    assert_eq!(
+        events::parse_event("openwindow>>55d1a2,2,spotify,Spotify, Premium\n"),
+        Some(Event::OpenWindow {
+            address: "0x55d1a2".to_string(),
+            workspace: "2".to_string(),
+            class: "spotify".to_string(),
+            title: "Spotify, Premium".to_string(),
+        })
+    );
+}
+
+#[tokio::test]
+async fn focus_policy_closes_duplicate_and_restores_managed_window() {
+    let fake = Arc::new(FakeBackend::new(
+        3,
+        vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID), window("0x2", "spotify", 3)],
+    ));
+    let window = managed("0x1");
+
+    let config = app_config("singleton_policy = \"focus\"");
+    run("singleton-focus", &fake, config, &window, vec![opened("0x2", "spotify", "Spotify")]).await;
+
+    assert!(fake.window("0x2").is_none());
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 3);
+    assert_eq!(fake.focused().as_deref(), Some("0x1"));
+}
+
+#[tokio::test]
+async fn adopt_policy_switches_to_new_window() {
+    let fake = Arc::new(FakeBackend::new(
+        3,
+        vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID), window("0x2", "spotify", 3)],
+    ));
+    let window = managed("0x1");
+
+    let config = app_config("singleton_policy = \"adopt\"");
+    let state = run("singleton-adopt", &fake, config, &window, vec![opened("0x2", "spotify", "New")]).await;
+
+    assert_eq!(window.address(), "0x2");
+    assert_eq!(window.get().title, "New");
+    assert_eq!(state.snapshot().title, "New");
+    // The tray is told to re-read the title
+    window.changed().await;
+    // The previous window is not left hidden without a tray icon
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 3);
+}
+
+#[tokio::test]
+async fn ignores_companions_and_other_classes() {
+    let fake = Arc::new(FakeBackend::new(
+        1,
+        vec![window("0x1", "spotify", 1), window("0x2", "spotify", 1), window("0x3", "kitty", 1)],
+    ));
+    let config = app_config("singleton_policy = \"focus\"\ncompanion_titles = [\"Mini Player\"]");
+
+    run(
+        "singleton-ignore",
+        &fake,
+        config,
+        &managed("0x1"),
+        vec![opened("0x2", "spotify", "Mini Player"), opened("0x3", "kitty", "kitty")],
+    )
+    .await;
+
+    assert!(fake.window("0x2").is_some());
+    assert!(fake.window("0x3").is_some());
+    assert!(fake.dispatched().is_empty());
+}
+
+#[tokio::test]
+async fn focus_policy_leaves_floating_windows_alone() {
+    let mut dialog = window("0x2", "spotify", 1);
+    dialog.floating = true;
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1), dialog]));
+
+    let config = app_config("singleton_policy = \"focus\"");
+    run("singleton-floating", &fake, config, &managed("0x1"), vec![opened("0x2", "spotify", "Preferences")]).await;
+
+    assert!(fake.window("0x2").is_some());
+    assert!(fake.dispatched().is_empty());
+}
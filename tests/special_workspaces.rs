@@ -0,0 +1,187 @@
+//! Special workspace assignment and collision handling between managed apps.
+
+mod common;
+
+use common::{app_config, window, workspace_named, FakeBackend, SPECIAL_WORKSPACE_ID};
+use hyprland_minimizer::config::Config;
+use hyprland_minimizer::hyprland::{self, ToggleAction, WindowInfo};
+use hyprland_minimizer::thumbnail::ThumbnailSlot;
+
+fn load(toml: &str) -> Config {
+    let mut config: Config = toml::from_str(toml).unwrap();
+    config.assign_special_workspaces();
+    config
+}
+
+#[test]
+fn apps_sharing_a_class_get_their_own_special_workspace() {
+    let config = load(
+        r#"
+        [apps.work]
+        name = "Work"
+        class = "firefox"
+        command = ["firefox", "-P", "work"]
+
+        [apps.home]
+        name = "Home"
+        class = "firefox"
+        command = ["firefox", "-P", "home"]
+
+        [apps.spotify]
+        name = "Spotify"
+        class = "spotify"
+        command = ["spotify"]
+        "#,
+    );
+
+    assert_eq!(config.apps["work"].special_workspace(), "firefox-work");
+    assert_eq!(config.apps["home"].special_workspace(), "firefox-home");
+    assert_eq!(config.apps["spotify"].special_workspace(), "spotify");
+}
+
+#[test]
+fn explicit_special_workspace_is_kept() {
+    let config = load(
+        r#"
+        [apps.work]
+        name = "Work"
+        class = "firefox"
+        command = ["firefox"]
+        special_workspace = "firefox"
+
+        [apps.home]
+        name = "Home"
+        class = "firefox"
+        command = ["firefox"]
+        "#,
+    );
+
+    assert_eq!(config.apps["work"].special_workspace(), "firefox");
+    assert_eq!(config.apps["home"].special_workspace(), "firefox-home");
+    // Renaming the implicit one resolved the collision
+    assert!(config.shared_special_workspaces().is_empty());
+}
+
+#[test]
+fn explicit_collisions_are_reported() {
+    let config = load(
+        r#"
+        [apps.work]
+        name = "Work"
+        class = "firefox"
+        command = ["firefox"]
+        special_workspace = "browser"
+
+        [apps.home]
+        name = "Home"
+        class = "chromium"
+        command = ["chromium"]
+        special_workspace = "browser"
+        "#,
+    );
+
+    assert_eq!(config.shared_special_workspaces(), vec![("browser", vec!["home", "work"])]);
+}
+
+#[tokio::test]
+async fn minimizes_to_configured_special_workspace() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1)]);
+    let config = app_config("special_workspace = \"music\"");
+
+    let action = hyprland::handle_window_toggle(&fake, &config, "0x1", &[], &ThumbnailSlot::default())
+        .await
+        .unwrap();
+
+    assert_eq!(action, ToggleAction::Minimized);
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("music"));
+}
+
+#[tokio::test]
+async fn toggles_managed_window_when_class_is_shared() {
+    let fake = FakeBackend::new(
+        1,
+        vec![window("0x1", "spotify", 1), window("0x2", "spotify", 1)],
+    );
+    let config = app_config("special_workspace = \"spotify-second\"");
+
+    hyprland::handle_window_toggle(&fake, &config, "0x2", &[], &ThumbnailSlot::default())
+        .await
+        .unwrap();
+
+    assert_eq!(fake.special_of("0x2").as_deref(), Some("spotify-second"));
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 1);
+}
+
+#[tokio::test]
+async fn restores_directly_from_shared_special_workspace() {
+    let mut other = window("0x2", "discord", SPECIAL_WORKSPACE_ID);
+    other.workspace = workspace_named(SPECIAL_WORKSPACE_ID, "spotify");
+    let fake = FakeBackend::new(3, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID), other]);
+
+    let action = hyprland::handle_window_toggle(&fake, &app_config(""), "0x1", &[], &ThumbnailSlot::default())
+        .await
+        .unwrap();
+
+    assert_eq!(action, ToggleAction::Restored);
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 3);
+    assert_eq!(fake.special_of("0x2").as_deref(), Some("spotify"));
+    assert!(fake.dispatched().iter().all(|d| !d.starts_with("togglespecialworkspace")));
+}
+
+/// Loads the "work" and "home" Firefox profiles, which share the class.
+fn profiles() -> Config {
+    load(
+        r#"
+        [apps.work]
+        name = "Work"
+        class = "firefox"
+        command = ["firefox", "-P", "work"]
+
+        [apps.home]
+        name = "Home"
+        class = "firefox"
+        command = ["firefox", "-P", "home"]
+        "#,
+    )
+}
+
+fn hidden_in(address: &str, special: &str) -> WindowInfo {
+    let mut hidden = window(address, "firefox", SPECIAL_WORKSPACE_ID);
+    hidden.workspace = workspace_named(SPECIAL_WORKSPACE_ID, special);
+    hidden
+}
+
+#[test]
+fn startup_skips_windows_of_other_apps_sharing_the_class() {
+    let config = profiles();
+    let home = &config.apps["home"];
+
+    // The work profile is hidden in its own special workspace
+    let clients = vec![hidden_in("0x1", "firefox-work")];
+    assert!(hyprland::find_app_window(home, &clients, &[]).is_none());
+
+    // A visible window managed by the work daemon is left alone too
+    let clients = vec![hidden_in("0x1", "firefox-work"), window("0x2", "firefox", 1)];
+    assert!(hyprland::find_app_window(home, &clients, &["0x2".to_string()]).is_none());
+
+    // A window hidden in the app's own special workspace wins over a visible one
+    let clients = vec![hidden_in("0x1", "firefox-work"), window("0x3", "firefox", 1), hidden_in("0x4", "firefox-home")];
+    let found = hyprland::find_app_window(home, &clients, &[]).unwrap();
+    assert_eq!(found.address, "0x4");
+}
+
+#[tokio::test]
+async fn toggle_does_not_fall_back_to_another_apps_hidden_window() {
+    let config = profiles();
+    let fake = FakeBackend::new(1, vec![hidden_in("0x1", "firefox-work"), window("0x2", "firefox", 1)]);
+
+    // The home window is gone; the work window is hidden and 0x2 is held by another daemon
+    let action = hyprland::handle_window_toggle(&fake, &config.apps["home"], "0x9", &["0x2".to_string()], &ThumbnailSlot::default())
+        .await
+        .unwrap();
+
+    assert_eq!(action, ToggleAction::NotFound);
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("firefox-work"));
+    assert_eq!(fake.window("0x2").unwrap().workspace.id, 1);
+    assert!(fake.dispatched().is_empty());
+}
@@ -0,0 +1,78 @@
+//! Daemon state file and usage statistics.
+
+mod common;
+
+use common::{daemon_state, temp_dir};
+use hyprland_minimizer::state::{self, DaemonState, Stats};
+use std::process::Command;
+
+#[test]
+fn reads_state_of_running_daemon() {
+    let dir = temp_dir("state-running");
+    let _state = daemon_state(&dir, "spotify");
+
+    let stats = state::read_from(&dir, "spotify").unwrap().unwrap();
+
+    assert_eq!(stats.pid, std::process::id());
+}
+
+#[test]
+fn removes_state_file_of_dead_daemon() {
+    let dir = temp_dir("state-stale");
+    let mut child = Command::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+    DaemonState::in_dir(&dir, "spotify", Stats { pid, ..Stats::default() });
+
+    assert!(state::read_from(&dir, "spotify").unwrap().is_none());
+    assert!(std::fs::read_dir(&dir).unwrap().next().is_none());
+}
+
+#[test]
+fn counts_restores_once_per_minimized_period() {
+    let dir = temp_dir("state-restores");
+    let state = daemon_state(&dir, "spotify");
+
+    state.set_minimized(true);
+    let since = state.snapshot().minimized_since;
+    state.set_minimized(true);
+    assert!(since.is_some());
+    assert_eq!(state.snapshot().minimized_since, since);
+
+    state.set_minimized(false);
+    state.set_minimized(false);
+    let stats = state::read_from(&dir, "spotify").unwrap().unwrap();
+    assert_eq!(stats.restore_count, 1);
+    assert_eq!(stats.minimized_since, None);
+}
+
+#[test]
+fn accumulates_minimized_time() {
+    let dir = temp_dir("state-minimized-time");
+    let stats = Stats {
+        pid: std::process::id(),
+        minimized_secs: 30,
+        minimized_since: Some(state::now_secs() - 60),
+        ..Stats::default()
+    };
+    let state = DaemonState::in_dir(&dir, "spotify", stats);
+    assert!(state.snapshot().total_minimized_secs() >= 90);
+
+    state.set_minimized(false);
+
+    let stats = state.snapshot();
+    assert!((90..95).contains(&stats.minimized_secs));
+    assert_eq!(stats.total_minimized_secs(), stats.minimized_secs);
+}
+
+#[test]
+fn lists_windows_held_by_other_daemons() {
+    let dir = temp_dir("state-held");
+    let work = daemon_state(&dir, "work");
+    work.set_window("0x1", "Work");
+    let home = daemon_state(&dir, "home");
+    home.set_window("0x2", "Home");
+
+    assert_eq!(state::held_addresses_from(&dir, "home"), vec!["0x1".to_string()]);
+    assert_eq!(home.held_by_others(), vec!["0x1".to_string()]);
+}
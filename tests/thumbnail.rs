@@ -0,0 +1,38 @@
+//! Thumbnail decoding and scaling.
+
+use hyprland_minimizer::thumbnail;
+
+#[test]
+fn parses_ppm_with_comments() {
+    let mut data = b"P6\n# grim\n2 1\n255\n".to_vec();
+    data.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+
+    let (width, height, rgb) = thumbnail::parse_ppm(&data).unwrap();
+
+    assert_eq!((width, height), (2, 1));
+    assert_eq!(rgb, [1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn rejects_truncated_ppm() {
+    assert!(thumbnail::parse_ppm(b"P6\n2 2\n255\n\x01\x02").is_err());
+    assert!(thumbnail::parse_ppm(b"P3\n1 1\n255\n0 0 0").is_err());
+}
+
+#[test]
+fn rejects_oversized_ppm_dimensions() {
+    let header = format!("P6\n{} {}\n255\n", usize::MAX, 2);
+    assert!(thumbnail::parse_ppm(header.as_bytes()).is_err());
+    let header = format!("P6\n{} {}\n255\n", usize::MAX / 3, 1);
+    assert!(thumbnail::parse_ppm(header.as_bytes()).is_err());
+}
+
+#[test]
+fn converts_to_argb_and_scales_down() {
+    let rgb: Vec<u8> = (0..4 * 2).flat_map(|i| [i as u8, 0, 0]).collect();
+
+    let (width, height, argb) = thumbnail::to_argb_pixmap(4, 2, &rgb, 2);
+
+    assert_eq!((width, height), (2, 1));
+    assert_eq!(argb, [0xff, 0, 0, 0, 0xff, 2, 0, 0]);
+}
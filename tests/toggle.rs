@@ -0,0 +1,252 @@
+//! Toggle logic against the fake compositor.
+
+mod common;
+
+use common::{app_config, monitor, window, workspace_named, FakeBackend, SPECIAL_WORKSPACE_ID};
+use hyprland_minimizer::hyprland::{self, ToggleAction};
+use hyprland_minimizer::config::AppConfig;
+use hyprland_minimizer::thumbnail::ThumbnailSlot;
+
+async fn toggle(fake: &FakeBackend, config: &AppConfig) -> ToggleAction {
+    hyprland::handle_window_toggle(fake, config, "0x1", &[], &ThumbnailSlot::default()).await.unwrap()
+}
+
+#[tokio::test]
+async fn minimizes_window_on_active_workspace() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1)]);
+
+    let action = toggle(&fake, &app_config("")).await;
+
+    assert_eq!(action, ToggleAction::Minimized);
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("spotify"));
+}
+
+#[tokio::test]
+async fn restores_window_from_special_workspace() {
+    let fake = FakeBackend::new(3, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]);
+
+    let action = toggle(&fake, &app_config("")).await;
+
+    assert_eq!(action, ToggleAction::Restored);
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 3);
+    assert_eq!(
+        fake.dispatched(),
+        [
+            "togglespecialworkspace spotify",
+            "movetoworkspace +0,address:0x1",
+            "focuswindow address:0x1",
+            "centerwindow",
+            "alterzorder top,address:0x1",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn runs_custom_restore_dispatch_with_placeholders() {
+    let fake = FakeBackend::new(3, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]);
+    let config = app_config(
+        "restore_dispatch = [\"resizeactive exact 60% 70%\", \"centerwindow\", \"tagwindow {class}-{workspace}\"]",
+    );
+
+    let action = toggle(&fake, &config).await;
+
+    // The window leaves the special workspace even though the list does not move it
+    assert_eq!(action, ToggleAction::Restored);
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 3);
+    assert_eq!(
+        fake.dispatched(),
+        [
+            "togglespecialworkspace spotify",
+            "movetoworkspace +0,address:0x1",
+            // Focused first so *active dispatchers act on the restored window
+            "focuswindow address:0x1",
+            "resizeactive exact 60% 70%",
+            "centerwindow",
+            "tagwindow spotify-3",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn empty_restore_dispatch_only_moves_and_focuses_window() {
+    let fake = FakeBackend::new(2, vec![window("0x1", "spotify", 5)]);
+
+    toggle(&fake, &app_config("restore_dispatch = []")).await;
+
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 2);
+    assert_eq!(fake.dispatched(), ["movetoworkspace +0,address:0x1", "focuswindow address:0x1"]);
+}
+
+#[tokio::test]
+async fn brings_window_from_other_workspace() {
+    let fake = FakeBackend::new(2, vec![window("0x1", "spotify", 5)]);
+
+    let action = toggle(&fake, &app_config("")).await;
+
+    assert_eq!(action, ToggleAction::Restored);
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 2);
+}
+
+#[tokio::test]
+async fn ignores_missing_window() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "firefox", 1)]);
+
+    let action = toggle(&fake, &app_config("")).await;
+
+    assert_eq!(action, ToggleAction::NotFound);
+    assert!(fake.dispatched().is_empty());
+}
+
+#[tokio::test]
+async fn minimize_skips_already_minimized_window() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]);
+
+    assert!(!hyprland::minimize_window(&fake, &app_config(""), "0x1", &ThumbnailSlot::default()).unwrap());
+    assert!(!hyprland::minimize_window(&fake, &app_config(""), "0x2", &ThumbnailSlot::default()).unwrap());
+    assert!(fake.dispatched().is_empty());
+}
+
+#[tokio::test]
+async fn follow_cursor_restores_to_monitor_under_cursor() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", SPECIAL_WORKSPACE_ID)]);
+    fake.set_layout(vec![monitor(0, 1), monitor(1920, 7)], (2500, 400));
+    let config = app_config("restore_policy = \"follow_cursor\"");
+
+    let action = toggle(&fake, &config).await;
+
+    assert_eq!(action, ToggleAction::Restored);
+    assert_eq!(fake.window("0x1").unwrap().workspace.id, 7);
+    assert_eq!(fake.dispatched()[0], "movetoworkspace 7,address:0x1");
+}
+
+#[tokio::test]
+async fn follow_cursor_minimizes_window_under_cursor() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 7)]);
+    fake.set_layout(vec![monitor(0, 1), monitor(1920, 7)], (2500, 400));
+    let config = app_config("restore_policy = \"follow_cursor\"");
+
+    let action = toggle(&fake, &config).await;
+
+    assert_eq!(action, ToggleAction::Minimized);
+}
+
+#[test]
+fn monitor_bounds_account_for_scale_and_rotation() {
+    let mut m = monitor(0, 1);
+    m.scale = 2.0;
+    assert!(m.contains(959, 539));
+    assert!(!m.contains(960, 100));
+
+    m.transform = 1;
+    assert!(m.contains(500, 900));
+    assert!(!m.contains(600, 100));
+}
+
+#[tokio::test]
+async fn companions_follow_the_main_window() {
+    let mut toolbox = window("0x2", "spotify", 1);
+    toolbox.title = "Toolbox".to_string();
+    let fake = FakeBackend::new(1, vec![
+        window("0x1", "spotify", 1),
+        toolbox,
+        window("0x3", "spotify-helper", 1),
+        window("0x4", "firefox", 1),
+    ]);
+    let config = app_config("companion_classes = [\"spotify-helper\"]\ncompanion_titles = [\"Toolbox\"]");
+
+    let action = toggle(&fake, &config).await;
+    assert_eq!(action, ToggleAction::Minimized);
+    for address in ["0x1", "0x2", "0x3"] {
+        assert_eq!(fake.special_of(address).as_deref(), Some("spotify"), "{}", address);
+    }
+    assert!(fake.special_of("0x4").is_none());
+
+    let action = toggle(&fake, &config).await;
+    assert_eq!(action, ToggleAction::Restored);
+    for address in ["0x1", "0x2", "0x3"] {
+        assert_eq!(fake.window(address).unwrap().workspace.id, 1, "{}", address);
+    }
+    assert_eq!(fake.focused().as_deref(), Some("0x1"));
+}
+
+#[tokio::test]
+async fn closing_the_group_closes_companions() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1), window("0x2", "spotify-helper", 1)]);
+    let config = app_config("companion_classes = [\"spotify-helper\"]");
+
+    hyprland::close_window_group(&fake, &config, "0x1").unwrap();
+
+    assert!(fake.window("0x1").is_none());
+    assert!(fake.window("0x2").is_none());
+}
+
+#[tokio::test]
+async fn title_matches_ignore_other_apps() {
+    let mut browser = window("0x2", "firefox", 1);
+    browser.title = "Toolbox - Mozilla Firefox".to_string();
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1), browser]);
+    let config = app_config("companion_titles = [\"Toolbox\"]");
+
+    toggle(&fake, &config).await;
+    assert_eq!(fake.window("0x2").unwrap().workspace.id, 1);
+
+    hyprland::close_window_group(&fake, &config, "0x1").unwrap();
+    assert!(fake.window("0x1").is_none());
+    assert!(fake.window("0x2").is_some());
+}
+
+#[tokio::test]
+async fn captures_thumbnail_before_minimizing_and_clears_on_restore() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1)]);
+    fake.set_layout(vec![monitor(0, 1)], (10, 10));
+    let config = app_config("tooltip_thumbnail = true");
+    let thumbnails = ThumbnailSlot::default();
+
+    hyprland::handle_window_toggle(&fake, &config, "0x1", &[], &thumbnails).await.unwrap();
+    // The fake tags the pixmap with the workspace the window was captured on
+    assert_eq!(thumbnails.get(), Some((1, 1, vec![0xff, 0, 0, 1])));
+
+    hyprland::handle_window_toggle(&fake, &config, "0x1", &[], &thumbnails).await.unwrap();
+    assert_eq!(thumbnails.get(), None);
+}
+
+#[tokio::test]
+async fn skips_thumbnail_for_covered_windows() {
+    let mut beside = window("0x2", "kitty", 1);
+    beside.at = [900, 100];
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1), beside]);
+    fake.set_layout(vec![monitor(0, 1)], (10, 10));
+    let config = app_config("tooltip_thumbnail = true");
+    let thumbnails = ThumbnailSlot::default();
+
+    // Side by side windows don't cover each other
+    hyprland::capture_thumbnail(&fake, &config, &fake.window("0x1").unwrap(), &thumbnails);
+    assert!(thumbnails.get().is_some());
+    thumbnails.clear();
+
+    let mut covering = window("0x3", "kitty", 1);
+    covering.at = [500, 400];
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1), covering]);
+    fake.set_layout(vec![monitor(0, 1)], (10, 10));
+    hyprland::capture_thumbnail(&fake, &config, &fake.window("0x1").unwrap(), &thumbnails);
+    assert_eq!(thumbnails.get(), None);
+
+    let mut scratchpad = monitor(0, 1);
+    scratchpad.special_workspace = workspace_named(SPECIAL_WORKSPACE_ID, "term");
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1)]);
+    fake.set_layout(vec![scratchpad], (10, 10));
+    hyprland::capture_thumbnail(&fake, &config, &fake.window("0x1").unwrap(), &thumbnails);
+    assert_eq!(thumbnails.get(), None);
+}
+
+#[tokio::test]
+async fn skips_thumbnail_for_windows_not_on_screen() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 4)]);
+    fake.set_layout(vec![monitor(0, 1)], (10, 10));
+    let thumbnails = ThumbnailSlot::default();
+
+    hyprland::minimize_window(&fake, &app_config("tooltip_thumbnail = true"), "0x1", &thumbnails).unwrap();
+
+    assert_eq!(thumbnails.get(), None);
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("spotify"));
+}
@@ -0,0 +1,165 @@
+//! Tray re-registration and reconnection against a private bus.
+//!
+//! These tests start their own `dbus-daemon` and are skipped when it is not
+//! installed.
+
+mod common;
+
+use common::{app_config, daemon_state, managed, temp_dir, window, FakeBackend};
+use hyprland_minimizer::dbus::{self, BusAddress, MenuModel, StatusNotifierItem, DBUS_WATCHER_NAME, DBUS_WATCHER_PATH};
+use hyprland_minimizer::i18n::{LabelOverrides, Labels};
+use hyprland_minimizer::thumbnail::ThumbnailSlot;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::time::{sleep, Duration, Instant};
+use zbus::{dbus_interface, Connection, ConnectionBuilder};
+
+const ITEM_NAME: &str = "org.kde.StatusNotifierItem.test.p1";
+
+/// A private `dbus-daemon`, killed when dropped.
+struct Bus {
+    child: Child,
+    address: String,
+}
+
+impl Bus {
+    /// Starts a bus listening on `socket`, or returns `None` if dbus-daemon is missing.
+    fn start(socket: &Path) -> Option<Self> {
+        let _ = std::fs::remove_file(socket);
+        let mut child = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .arg(format!("--address=unix:path={}", socket.display()))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        // The address is printed once the bus accepts connections
+        let mut line = String::new();
+        BufReader::new(child.stdout.take()?).read_line(&mut line).ok()?;
+        let address = format!("unix:path={}", socket.display());
+        Some(Self { child, address })
+    }
+
+    fn address(&self) -> BusAddress {
+        BusAddress::Custom(self.address.clone())
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// StatusNotifierWatcher recording the items registered with it.
+#[derive(Clone, Default)]
+struct FakeWatcher {
+    registered: Arc<Mutex<Vec<String>>>,
+}
+
+#[dbus_interface(name = "org.kde.StatusNotifierWatcher")]
+impl FakeWatcher {
+    fn register_status_notifier_item(&self, service: &str) {
+        self.registered.lock().unwrap().push(service.to_string());
+    }
+}
+
+impl FakeWatcher {
+    /// Claims the watcher name on `bus`; the name is released when the connection is dropped.
+    async fn serve(&self, bus: &Bus) -> Connection {
+        ConnectionBuilder::address(bus.address.as_str())
+            .unwrap()
+            .name(DBUS_WATCHER_NAME)
+            .unwrap()
+            .serve_at(DBUS_WATCHER_PATH, self.clone())
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+    }
+
+    /// Waits until `ITEM_NAME` registers, giving up after `secs` seconds.
+    async fn wait_for_item(&self, secs: u64) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(secs);
+        while Instant::now() < deadline {
+            if self.registered.lock().unwrap().iter().any(|s| s == ITEM_NAME) {
+                return true;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        false
+    }
+}
+
+/// Serves the tray objects on `bus`, registers them and starts the watchdog.
+async fn start_tray(test: &str, bus: &Bus) {
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1)]));
+    let exit_notify = Arc::new(Notify::new());
+    let item = StatusNotifierItem {
+        backend: fake.clone(),
+        app_config: app_config(""),
+        window_info: managed("0x1"),
+        exit_notify: exit_notify.clone(),
+        icon_theme_path: String::new(),
+        thumbnails: ThumbnailSlot::default(),
+    };
+    let menu = Arc::new(MenuModel {
+        backend: fake,
+        app_config: app_config(""),
+        window_info: managed("0x1"),
+        exit_notify,
+        state: daemon_state(&temp_dir(&format!("{}-state", test)), "spotify"),
+        labels: Labels::for_language("en", &LabelOverrides::default()),
+        thumbnails: ThumbnailSlot::default(),
+    });
+
+    let conn = dbus::serve(&bus.address(), ITEM_NAME, item.clone(), &menu).await.unwrap();
+    dbus::register_with_watcher(&conn, ITEM_NAME).await.unwrap();
+    tokio::spawn(dbus::watchdog(bus.address(), conn, ITEM_NAME.to_string(), item, menu));
+}
+
+#[tokio::test]
+async fn registers_again_when_watcher_restarts() {
+    let dir = temp_dir("watchdog-watcher");
+    let Some(bus) = Bus::start(&dir.join("bus")) else {
+        eprintln!("dbus-daemon not available, skipping");
+        return;
+    };
+    let first = FakeWatcher::default();
+    let first_conn = first.serve(&bus).await;
+    start_tray("watchdog-watcher", &bus).await;
+    assert!(first.wait_for_item(5).await);
+
+    drop(first_conn);
+    let second = FakeWatcher::default();
+    let _second_conn = second.serve(&bus).await;
+
+    assert!(second.wait_for_item(5).await);
+}
+
+#[tokio::test]
+async fn reconnects_when_bus_restarts() {
+    let dir = temp_dir("watchdog-bus");
+    let Some(bus) = Bus::start(&dir.join("bus")) else {
+        eprintln!("dbus-daemon not available, skipping");
+        return;
+    };
+    let first = FakeWatcher::default();
+    let _first_conn = first.serve(&bus).await;
+    start_tray("watchdog-bus", &bus).await;
+    assert!(first.wait_for_item(5).await);
+    // Let the watchdog settle into its periodic checks before the bus goes away
+    sleep(Duration::from_millis(500)).await;
+
+    drop(bus);
+    let bus = Bus::start(&dir.join("bus")).unwrap();
+    let second = FakeWatcher::default();
+    let _second_conn = second.serve(&bus).await;
+
+    // Detection takes up to one check interval, plus the reconnect delay
+    assert!(second.wait_for_item(20).await);
+}
@@ -0,0 +1,180 @@
+//! Window closure and focus-driven behavior against the fake compositor.
+
+mod common;
+
+use common::{app_config, daemon_state, managed, temp_dir, window, FakeBackend, Scripted};
+use hyprland_minimizer::auto_minimize;
+use hyprland_minimizer::config::AppConfig;
+use hyprland_minimizer::events::{self, Event};
+use hyprland_minimizer::hyprland;
+use hyprland_minimizer::thumbnail::ThumbnailSlot;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+fn auto_config(on_unfocus: bool, after_secs: Option<u64>) -> AppConfig {
+    app_config(&format!(
+        "auto_minimize_on_unfocus = {}\n{}",
+        on_unfocus,
+        after_secs.map(|s| format!("auto_minimize_after_secs = {}", s)).unwrap_or_default()
+    ))
+}
+
+fn focus(address: &str) -> Event {
+    Event::ActiveWindow { address: Some(address.to_string()) }
+}
+
+#[tokio::test(start_paused = true)]
+async fn watch_returns_when_window_closes() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1)]);
+    fake.schedule(3, Scripted::Close("0x1".to_string()));
+
+    let mut updates = 0;
+    hyprland::watch_window(&fake, &managed("0x1"), Duration::from_secs(2), |_| updates += 1)
+        .await
+        .unwrap();
+
+    assert_eq!(updates, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn watch_stops_on_backend_error() {
+    let fake = FakeBackend::new(1, vec![window("0x1", "spotify", 1)]);
+    fake.schedule(2, Scripted::Fail);
+
+    let result = hyprland::watch_window(&fake, &managed("0x1"), Duration::from_secs(2), |_| {}).await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn auto_minimizes_on_focus_loss() {
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1), window("0x2", "kitty", 1)]));
+    let state = daemon_state(&temp_dir("unfocus"), "spotify");
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tx.send(focus("0x1")).unwrap();
+    tx.send(focus("0x2")).unwrap();
+    drop(tx);
+    auto_minimize::run(fake.clone(), rx, auto_config(true, None), managed("0x1"), state.clone(), ThumbnailSlot::default()).await;
+
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("spotify"));
+    assert!(state.snapshot().minimized_since.is_some());
+    state.remove();
+}
+
+#[tokio::test(start_paused = true)]
+async fn auto_minimizes_window_focused_before_start() {
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1), window("0x2", "kitty", 1)]));
+    fake.focus("0x1");
+    let state = daemon_state(&temp_dir("prefocused"), "spotify");
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    // No focus event for the managed window arrives before it loses focus
+    tx.send(focus("0x2")).unwrap();
+    drop(tx);
+    auto_minimize::run(fake.clone(), rx, auto_config(true, None), managed("0x1"), state.clone(), ThumbnailSlot::default()).await;
+
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("spotify"));
+    state.remove();
+}
+
+#[tokio::test(start_paused = true)]
+async fn auto_minimizes_after_inactivity() {
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1), window("0x2", "kitty", 1)]));
+    let state = daemon_state(&temp_dir("inactivity"), "spotify");
+    let (tx, rx) = mpsc::unbounded_channel();
+    let task = tokio::spawn(auto_minimize::run(
+        fake.clone(),
+        rx,
+        auto_config(false, Some(60)),
+        managed("0x1"),
+        state.clone(),
+        ThumbnailSlot::default(),
+    ));
+
+    tx.send(focus("0x1")).unwrap();
+    tx.send(focus("0x2")).unwrap();
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    assert!(fake.special_of("0x1").is_none());
+
+    tokio::time::sleep(Duration::from_secs(31)).await;
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("spotify"));
+
+    drop(tx);
+    task.await.unwrap();
+    state.remove();
+}
+
+#[tokio::test(start_paused = true)]
+async fn refocusing_cancels_inactivity_timer() {
+    let fake = Arc::new(FakeBackend::new(1, vec![window("0x1", "spotify", 1), window("0x2", "kitty", 1)]));
+    let state = daemon_state(&temp_dir("refocus"), "spotify");
+    let (tx, rx) = mpsc::unbounded_channel();
+    let task = tokio::spawn(auto_minimize::run(
+        fake.clone(),
+        rx,
+        auto_config(false, Some(60)),
+        managed("0x1"),
+        state.clone(),
+        ThumbnailSlot::default(),
+    ));
+
+    tx.send(focus("0x1")).unwrap();
+    tx.send(focus("0x2")).unwrap();
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    tx.send(focus("0x1")).unwrap();
+    tokio::time::sleep(Duration::from_secs(60)).await;
+
+    assert!(fake.special_of("0x1").is_none());
+    drop(tx);
+    task.await.unwrap();
+    state.remove();
+}
+
+#[tokio::test]
+async fn fan_out_delivers_every_event_to_each_consumer() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let [mut first, mut second] = events::fan_out(rx);
+
+    tx.send(focus("0x1")).unwrap();
+    tx.send(focus("0x2")).unwrap();
+    drop(tx);
+
+    for consumer in [&mut first, &mut second] {
+        assert_eq!(consumer.recv().await, Some(focus("0x1")));
+        assert_eq!(consumer.recv().await, Some(focus("0x2")));
+        assert_eq!(consumer.recv().await, None);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn dialogs_of_the_app_keep_window_focused() {
+    let mut dialog = window("0x2", "spotify", 1);
+    dialog.floating = true;
+    let fake = Arc::new(FakeBackend::new(
+        1,
+        vec![window("0x1", "spotify", 1), dialog, window("0x3", "kitty", 1)],
+    ));
+    let state = daemon_state(&temp_dir("dialog"), "spotify");
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tx.send(focus("0x1")).unwrap();
+    tx.send(focus("0x2")).unwrap();
+    drop(tx);
+    auto_minimize::run(fake.clone(), rx, auto_config(true, None), managed("0x1"), state.clone(), ThumbnailSlot::default()).await;
+
+    assert!(fake.special_of("0x1").is_none());
+    assert!(fake.dispatched().is_empty());
+
+    // Leaving the dialog for another app still minimizes the window
+    let (tx, rx) = mpsc::unbounded_channel();
+    tx.send(focus("0x1")).unwrap();
+    tx.send(focus("0x2")).unwrap();
+    tx.send(focus("0x3")).unwrap();
+    drop(tx);
+    auto_minimize::run(fake.clone(), rx, auto_config(true, None), managed("0x1"), state.clone(), ThumbnailSlot::default()).await;
+
+    assert_eq!(fake.special_of("0x1").as_deref(), Some("spotify"));
+    state.remove();
+}